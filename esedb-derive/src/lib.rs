@@ -0,0 +1,168 @@
+//! `#[derive(EseRecord)]` for the esedb crate: maps the fields of a struct onto the columns of an
+//! ESE table.
+//! Copyright 2016-2019 by William R. Fraser
+//!
+//! Each field is read from and written to the column with the same name, unless changed with
+//! attributes:
+//!
+//!   - `#[ese(rename = "Column")]` uses a different column name.
+//!   - `#[ese(multi)]` on a `Vec<T>` field reads and writes every value of a multi-valued column.
+//!   - `#[ese(with = "module")]` converts the value with `module::read(Option<Vec<u8>>) ->
+//!     Result<T, JetError>` and `module::write(&T) -> Option<Vec<u8>>` instead of the built-in
+//!     conversions. `None` stands for NULL.
+//!   - `#[ese(skip)]` leaves the field out; it is filled with `Default::default()` when reading.
+//!
+//! Fields of type `Option<T>` are NULL when `None`. All other fields must not be NULL.
+
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericArgument, LitStr, Path,
+    PathArguments, Type};
+
+#[proc_macro_derive(EseRecord, attributes(ese))]
+pub fn derive_ese_record(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    multi: bool,
+    with: Option<Path>,
+    skip: bool,
+}
+
+fn parse_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in &field.attrs {
+        if !attr.path().is_ident("ese") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attrs.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("multi") {
+                attrs.multi = true;
+            } else if meta.path.is_ident("with") {
+                attrs.with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                attrs.skip = true;
+            } else {
+                return Err(meta.error("unknown ese attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+/// If `ty` is `wrapper<T>` (such as `Option<T>`), get `T`.
+fn inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match *ty {
+        Type::Path(ref p) if p.qself.is_none() => &p.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                GenericArgument::Type(ref t) => Some(t),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(input,
+                    "EseRecord can only be derived for structs with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(input,
+                "EseRecord can only be derived for structs")),
+    };
+
+    let mut specs = vec![];
+    let mut reads = vec![];
+    let mut writes = vec![];
+    let mut index = 0usize;
+    for field in fields {
+        let attrs = parse_attrs(field)?;
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+
+        if attrs.skip {
+            reads.push(quote! { #ident: ::std::default::Default::default() });
+            continue;
+        }
+
+        let name = attrs.rename.clone().unwrap_or_else(|| ident.to_string());
+        let id = quote! { columns.id(#index) };
+        index += 1;
+
+        if let Some(ref with) = attrs.with {
+            specs.push(quote! { ::esedb::ColumnSpec::untyped(#name) });
+            reads.push(quote! {
+                #ident: #with::read(table.retrieve_column_bytes_opt::<u8>(#id)?)?
+            });
+            writes.push(quote! {
+                match #with::write(&self.#ident) {
+                    Some(data) => record.set_bytes(#id, &data)?,
+                    None => record.set_null(#id)?,
+                }
+            });
+        } else if attrs.multi {
+            let inner = inner_type(ty, "Vec").ok_or_else(|| syn::Error::new_spanned(ty,
+                    "#[ese(multi)] fields must be of type Vec<T>"))?;
+            specs.push(quote! { ::esedb::ColumnSpec::new::<#inner, _>(#name) });
+            reads.push(quote! { #ident: ::esedb::read_multi(table, #id)? });
+            writes.push(quote! { ::esedb::write_multi(record, #id, &self.#ident)?; });
+        } else if let Some(inner) = inner_type(ty, "Option") {
+            specs.push(quote! { ::esedb::ColumnSpec::new::<#inner, _>(#name) });
+            reads.push(quote! { #ident: ::esedb::read_optional(table, #id)? });
+            writes.push(quote! { ::esedb::write_optional(record, #id, &self.#ident)?; });
+        } else {
+            specs.push(quote! { ::esedb::ColumnSpec::new::<#ty, _>(#name) });
+            reads.push(quote! { #ident: ::esedb::read_column(table, #id)? });
+            writes.push(quote! { ::esedb::write_column(record, #id, &self.#ident)?; });
+        }
+    }
+
+    let count = index;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::esedb::EseRecord for #name #ty_generics #where_clause {
+            fn column_specs() -> ::std::vec::Vec<::esedb::ColumnSpec> {
+                vec![#(#specs),*]
+            }
+
+            fn read(table: &::esedb::JetTable, columns: &::esedb::ColumnSet)
+                    -> ::std::result::Result<Self, ::esedb::JetError> {
+                ::esedb::check_column_count(columns, #count)?;
+                Ok(#name {
+                    #(#reads),*
+                })
+            }
+
+            fn write(&self, record: &::esedb::RecordBuilder, columns: &::esedb::ColumnSet)
+                    -> ::std::result::Result<(), ::esedb::JetError> {
+                ::esedb::check_column_count(columns, #count)?;
+                #(#writes)*
+                Ok(())
+            }
+        }
+    })
+}
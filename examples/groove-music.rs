@@ -11,28 +11,23 @@ use esedb::*;
 use std::collections::BTreeMap;
 use std::env;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 const APPDATA_PATH: &str = r"Packages\Microsoft.ZuneMusic_8wekyb3d8bbwe\LocalState\Database\";
 
-struct EntPlatDb<'a> {
-    // raw pointers have to be used here because Rust's lifetime system doesn't accomodate having
-    // these three things in one struct (because destruction order would be unspecified).
-    instance: *mut JetInstance,
-    session: *mut JetSession<'a>,
-    database: *mut JetDatabase<'a>,
+struct EntPlatDb {
+    // The database handle keeps the session and instance it was opened with alive, and closes
+    // everything in the right order when it's dropped.
+    database: OwnedDatabase,
 }
 
-impl<'a> EntPlatDb<'a> {
+impl EntPlatDb {
     pub fn get_database_root_path() -> PathBuf {
         let localappdata = env::var_os("localappdata").expect("%LOCALAPPDATA% missing");
         PathBuf::from(localappdata).join(APPDATA_PATH)
     }
 
-    pub fn new(path: &Path) -> Result<EntPlatDb<'a>, JetError> {
-        let pinstance: *mut JetInstance;
-        let psession: *mut JetSession<'a>;
-        let pdatabase: *mut JetDatabase<'a>;
-        
+    pub fn new(path: &Path) -> Result<EntPlatDb, JetError> {
         let mut instance = JetInstance::new();
         instance.set_int_parameter(JET_paramDatabasePageSize, 8192)?;
         instance.set_int_parameter(JET_paramEnableAdvanced, 1)?;
@@ -41,53 +36,33 @@ impl<'a> EntPlatDb<'a> {
         instance.init_engine(&"esedb-rs-example".into())?;
         set_database_paths(&mut instance, path)?;
         instance.init()?;
-        pinstance = Box::into_raw(Box::new(instance));
 
-        let session = unsafe { (*pinstance).create_session()? };
-        psession = Box::into_raw(Box::new(session));
+        let session = OwnedSession::new(Arc::new(instance))?;
 
         let file_path: WideString = path.join("EntClientDb.edb").as_os_str().into();
-        let database = unsafe {
-            (*psession).open_database(&file_path, DatabaseAccessMode::ReadOnly)?
-        };
-        pdatabase = Box::into_raw(Box::new(database));
+        let database = session.open_database(&file_path, DatabaseAccessMode::ReadOnly)?;
 
         Ok(EntPlatDb {
-            instance: pinstance,
-            session: psession,
-            database: pdatabase,
+            database,
         })
     }
 
     pub fn get_artists(&self) -> Result<BTreeMap<u32, Artist>, JetError> {
-        unsafe { get_artists(&*self.database) }
+        get_artists(&self.database)
     }
 
     pub fn get_albums(&self) -> Result<BTreeMap<u32, Album>, JetError> {
-        unsafe { get_albums(&*self.database) }
+        get_albums(&self.database)
     }
 
     pub fn get_tracks(&self, start_id: Option<u32>) -> Result<BTreeMap<u32, Track>, JetError> {
-        unsafe { get_tracks(&*self.database, start_id) }
-    }
-}
-
-impl<'a> Drop for EntPlatDb<'a> {
-    fn drop(&mut self) {
-        use std::intrinsics::drop_in_place;
-        unsafe {
-            drop_in_place(self.database);
-            drop_in_place(self.session);
-            drop_in_place(self.instance);
-        }
+        get_tracks(&self.database, start_id)
     }
 }
 
 fn set_database_paths(instance: &mut JetInstance, path: &Path) -> Result<(), JetError> {
+    instance.configure(&InstanceConfig::new().all_paths(path))?;
     let wpath: WideString = path.join("").as_os_str().into();
-    instance.set_string_parameter(JET_paramSystemPath, &wpath)?;
-    instance.set_string_parameter(JET_paramLogFilePath, &wpath)?;
-    instance.set_string_parameter(JET_paramTempPath, &wpath)?;
     instance.set_string_parameter(JET_paramAlternateDatabaseRecoveryPath, &wpath)?;
     Ok(())
 }
@@ -165,11 +140,13 @@ fn get_tracks(db: &JetDatabase, start_id: Option<u32>) -> Result<BTreeMap<u32, T
     let mut map = BTreeMap::new();
 
     let table = db.open_table(&"tblTrack".into())?;
-    let id_colid = table.get_column_id(&"Id".into())?;
-    let title_colid = table.get_column_id(&"Title".into())?;
-    let artist_id_colid = table.get_column_id(&"ArtistId".into())?;
-    let album_id_colid = table.get_column_id(&"AlbumId".into())?;
-    let collection_state_colid = table.get_column_id(&"CollectionState".into())?;
+    let mut columns = [
+        ColumnRequest::new(table.get_column_id(&"Id".into())?, 4),
+        ColumnRequest::new(table.get_column_id(&"Title".into())?, 256),
+        ColumnRequest::new(table.get_column_id(&"ArtistId".into())?, 4),
+        ColumnRequest::new(table.get_column_id(&"AlbumId".into())?, 4),
+        ColumnRequest::new(table.get_column_id(&"CollectionState".into())?, 1),
+    ];
 
     if let Some(id) = start_id {
         // no need to set an index; the ID is the primary key and therefore the default index.
@@ -177,11 +154,13 @@ fn get_tracks(db: &JetDatabase, start_id: Option<u32>) -> Result<BTreeMap<u32, T
     }
 
     loop {
-        let id = table.retrieve(id_colid)?;
-        let title = table.retrieve_wstring(title_colid)?.to_string_lossy();
-        let artist_id = table.retrieve(artist_id_colid)?;
-        let album_id = table.retrieve(album_id_colid)?;
-        let collection_state = table.retrieve(collection_state_colid)?;
+        // Get all five columns at once, instead of one at a time.
+        table.retrieve_columns(&mut columns)?;
+        let id = columns[0].value()?;
+        let title = columns[1].wstring().to_string_lossy();
+        let artist_id = columns[2].value()?;
+        let album_id = columns[3].value()?;
+        let collection_state = columns[4].value()?;
 
         map.insert(id, Track {
             title,
@@ -0,0 +1,135 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+
+use futures::channel::{mpsc, oneshot};
+use futures::executor::block_on;
+use futures::{Future, FutureExt, SinkExt, Stream};
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+
+type Job = Box<dyn FnOnce(&mut PooledSession) + Send>;
+
+/// A session running on its own worker thread, for use from async code.
+///
+/// Operations are sent to the worker as closures and their results come back as futures, so the
+/// async executor's threads never block on the database. Everything done in one closure (such as a
+/// whole transaction) runs on the session without interruption. Databases and tables opened
+/// through `PooledSession::database` and `PooledSession::table` stay open between calls.
+///
+/// If a closure panics, its future (or stream) gives `JET_errCallbackFailed`, and the worker goes
+/// on to the next job.
+///
+/// Jobs run one at a time, in order. A `scan` whose stream isn't being read stops once its buffer
+/// is full, and every job queued after it waits until it finishes, so awaiting `run` while holding
+/// an undrained scan stream deadlocks. Read the stream to the end, or drop it, first.
+///
+/// Dropping an `AsyncSession` doesn't wait for the worker: it finishes the jobs already queued and
+/// then exits on its own.
+#[derive(Debug)]
+pub struct AsyncSession {
+    jobs: std_mpsc::Sender<Job>,
+}
+
+fn job_panicked() -> JetError {
+    JetError::from(JET_errCallbackFailed)
+}
+
+impl AsyncSession {
+    /// Start a worker thread with a new session on the given (initialized) instance.
+    pub fn new(instance: Arc<JetInstance>) -> Result<AsyncSession, JetError> {
+        let (jobs_tx, jobs_rx) = std_mpsc::channel::<Job>();
+        let (ready_tx, ready_rx) = std_mpsc::channel();
+        let thread = thread::spawn(move || {
+            let pool = SessionPool::new(instance, 1);
+            let mut session = match pool.get() {
+                Ok(session) => {
+                    let _ = ready_tx.send(Ok(()));
+                    session
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            for job in jobs_rx {
+                // A panicking job drops its result sender, so its caller gets an error instead.
+                if panic::catch_unwind(AssertUnwindSafe(|| job(&mut session))).is_err() {
+                    error!("AsyncSession job panicked; continuing with the next one");
+                }
+            }
+            debug!("AsyncSession worker exiting");
+        });
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(AsyncSession {
+                jobs: jobs_tx,
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => {
+                error!("AsyncSession worker thread panicked while starting");
+                let _ = thread.join();
+                Err(job_panicked())
+            }
+        }
+    }
+
+    /// Run a closure on the worker thread's session, returning a future of its result.
+    pub fn run<T, F>(&self, f: F) -> impl Future<Output = Result<T, JetError>>
+        where F: FnOnce(&mut PooledSession) -> Result<T, JetError> + Send + 'static,
+              T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job: Job = Box::new(move |session| {
+            let _ = tx.send(f(session));
+        });
+        // If the worker is gone, the job (and its sender) is dropped, which cancels `rx`.
+        let _ = self.jobs.send(job);
+        rx.map(|result| result.unwrap_or_else(|_canceled| Err(job_panicked())))
+    }
+
+    /// Scan a whole table in the order of its current index, calling `f` on each record on the
+    /// worker thread and streaming the results back. At most `buffer` results are queued up ahead
+    /// of the consumer.
+    pub fn scan<T, F>(&self, path: WideString, mode: DatabaseAccessMode, tablename: WideString,
+            buffer: usize, mut f: F) -> impl Stream<Item = Result<T, JetError>>
+        where F: FnMut(&JetTable) -> Result<T, JetError> + Send + 'static,
+              T: Send + 'static,
+    {
+        let (mut tx, rx) = mpsc::channel(buffer);
+        let job: Job = Box::new(move |session| {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                let table = session.table(&path, mode, &tablename)?;
+                match table.move_first() {
+                    Err(ref e) if e.code == JET_errNoCurrentRecord => return Ok(()),
+                    other => other?,
+                }
+                loop {
+                    let row = f(table);
+                    // If the stream was dropped, stop scanning.
+                    if block_on(tx.send(row)).is_err() {
+                        return Ok(());
+                    }
+                    match table.move_next() {
+                        Ok(()) => (),
+                        Err(ref e) if e.code == JET_errNoCurrentRecord => return Ok(()),
+                        Err(e) => return Err(e),
+                    }
+                }
+            })).unwrap_or_else(|_| Err(job_panicked()));
+            if let Err(e) = result {
+                let _ = block_on(tx.send(Err(e)));
+            }
+        });
+        // If the worker is gone, the job (and its sender) is dropped, which ends the stream.
+        let _ = self.jobs.send(job);
+        rx
+    }
+}
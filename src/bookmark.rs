@@ -0,0 +1,70 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+// The largest possible bookmark or secondary key: a key from a database with 32 KiB pages
+// (JET_cbKeyMostMost), plus the prefix byte. Buffers this big never need a second call to fetch.
+pub(crate) const BOOKMARK_MAX: usize = 2001;
+
+/// An owned copy of the bookmark of a record, which can be used to return to that record later
+/// with `JetTable::goto_bookmark`.
+///
+/// Bookmarks are normalized primary keys, so comparing them byte-wise orders them the same way the
+/// records are ordered in the primary index.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bookmark {
+    data: Vec<u8>,
+}
+
+impl Bookmark {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<Vec<u8>> for Bookmark {
+    fn from(data: Vec<u8>) -> Bookmark {
+        Bookmark { data }
+    }
+}
+
+/// An owned copy of the position of a record in a secondary index: the normalized secondary key
+/// plus the primary bookmark of the record.
+///
+/// These order the same way the index entries are ordered in the secondary index.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SecondaryIndexBookmark {
+    secondary_key: Vec<u8>,
+    primary: Bookmark,
+}
+
+impl SecondaryIndexBookmark {
+    pub fn new(secondary_key: Vec<u8>, primary: Bookmark) -> SecondaryIndexBookmark {
+        SecondaryIndexBookmark {
+            secondary_key,
+            primary,
+        }
+    }
+
+    pub fn secondary_key(&self) -> &[u8] {
+        &self.secondary_key
+    }
+
+    pub fn primary(&self) -> &Bookmark {
+        &self.primary
+    }
+}
+
+#[test]
+fn test_bookmark_ordering() {
+    let a = Bookmark::from(vec![0x7f, 0x80]);
+    let b = Bookmark::from(vec![0x7f, 0x80, 0x00]);
+    let c = Bookmark::from(vec![0x7f, 0x81]);
+    assert!(a < b);
+    assert!(b < c);
+
+    let x = SecondaryIndexBookmark::new(vec![0x10], c.clone());
+    let y = SecondaryIndexBookmark::new(vec![0x10, 0x00], a.clone());
+    let z = SecondaryIndexBookmark::new(vec![0x10], a);
+    assert!(z < x);
+    assert!(x < y);
+}
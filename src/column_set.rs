@@ -0,0 +1,232 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+use super::util::CP_UNICODE;
+
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+
+/// Rust types which can be read from columns, and the column types they're compatible with.
+pub trait ColumnValueType {
+    fn column_types() -> &'static [ColumnType];
+
+    /// The code page a text column must have to hold this type, if it matters.
+    fn codepage() -> Option<u16> {
+        None
+    }
+}
+
+macro_rules! column_value {
+    ($t:ty => $($coltyp:ident),+) => {
+        impl ColumnValueType for $t {
+            fn column_types() -> &'static [ColumnType] {
+                &[$(ColumnType::$coltyp),+]
+            }
+        }
+    }
+}
+
+column_value!(bool => Bit);
+column_value!(u8 => UnsignedByte, Bit);
+column_value!(i16 => Short);
+column_value!(u16 => UnsignedShort, Short);
+column_value!(i32 => Long);
+column_value!(u32 => UnsignedLong, Long);
+column_value!(i64 => LongLong, Currency);
+column_value!(u64 => LongLong, Currency);
+column_value!(f32 => IEEESingle);
+column_value!(f64 => IEEEDouble, DateTime);
+column_value!([u8; 16] => GUID);
+column_value!(Vec<u8> => Binary, LongBinary);
+
+macro_rules! unicode_value {
+    ($($t:ty),+) => {
+        $(
+            impl ColumnValueType for $t {
+                fn column_types() -> &'static [ColumnType] {
+                    &[ColumnType::Text, ColumnType::LongText]
+                }
+
+                fn codepage() -> Option<u16> {
+                    Some(CP_UNICODE)
+                }
+            }
+        )+
+    }
+}
+
+unicode_value!(WideString, String, OsString);
+
+impl<T: ColumnValueType> ColumnValueType for Option<T> {
+    fn column_types() -> &'static [ColumnType] {
+        T::column_types()
+    }
+
+    fn codepage() -> Option<u16> {
+        T::codepage()
+    }
+}
+
+/// A column name and the types of column it's expected to be.
+#[derive(Debug, Clone)]
+pub struct ColumnSpec {
+    pub name: WideString,
+    pub types: &'static [ColumnType],
+    /// The code page the column must have if it's a text column, or `None` for any.
+    pub codepage: Option<u16>,
+}
+
+impl ColumnSpec {
+    /// A column which will be read as the Rust type `T`.
+    pub fn new<T: ColumnValueType, S: Into<WideString>>(name: S) -> ColumnSpec {
+        ColumnSpec {
+            name: name.into(),
+            types: T::column_types(),
+            codepage: T::codepage(),
+        }
+    }
+
+    /// A column which can be any type.
+    pub fn untyped<S: Into<WideString>>(name: S) -> ColumnSpec {
+        ColumnSpec {
+            name: name.into(),
+            types: &[],
+            codepage: None,
+        }
+    }
+}
+
+/// Why a `ColumnSet` couldn't be resolved against a table.
+#[derive(Debug)]
+pub enum ColumnSetError {
+    /// The table has no column with this name.
+    Missing(WideString),
+    /// The column's type doesn't match any expected for it.
+    WrongType {
+        column: WideString,
+        expected: &'static [ColumnType],
+        actual: JET_COLTYP,
+    },
+    /// The text column's code page doesn't match the one expected for it.
+    WrongCodePage {
+        column: WideString,
+        expected: u16,
+        actual: u16,
+    },
+    /// The column ID differs from when the set was resolved, so the table's schema has changed.
+    Changed(WideString),
+    Jet(JetError),
+}
+
+impl From<JetError> for ColumnSetError {
+    fn from(e: JetError) -> ColumnSetError {
+        ColumnSetError::Jet(e)
+    }
+}
+
+impl Display for ColumnSetError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ColumnSetError::Missing(ref column) => write!(f, "no column named {}", column),
+            ColumnSetError::WrongType { ref column, expected, actual } =>
+                write!(f, "column {} has type {}, expected one of {:?}", column, actual, expected),
+            ColumnSetError::WrongCodePage { ref column, expected, actual } =>
+                write!(f, "column {} has code page {}, expected {}", column, actual, expected),
+            ColumnSetError::Changed(ref column) =>
+                write!(f, "column {} has changed since the column set was resolved", column),
+            ColumnSetError::Jet(ref e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl Error for ColumnSetError {
+    fn description(&self) -> &str {
+        "ESE DB column set error"
+    }
+}
+
+/// A list of columns resolved to column IDs once, for reading the same columns from many records,
+/// or from the same table opened again later, by any session.
+#[derive(Debug, Clone)]
+pub struct ColumnSet {
+    specs: Vec<ColumnSpec>,
+    ids: Vec<JET_COLUMNID>,
+}
+
+impl ColumnSet {
+    /// Look up the columns in a table, checking that their types match.
+    pub fn resolve(table: &JetTable, specs: Vec<ColumnSpec>) -> Result<ColumnSet, ColumnSetError> {
+        let mut ids = Vec::with_capacity(specs.len());
+        for spec in &specs {
+            ids.push(lookup(table, spec)?);
+        }
+        Ok(ColumnSet {
+            specs,
+            ids,
+        })
+    }
+
+    /// Check that the columns still have the same IDs and types in the given table, which might
+    /// be a different open of the table the set was resolved against.
+    pub fn verify(&self, table: &JetTable) -> Result<(), ColumnSetError> {
+        for (spec, &id) in self.specs.iter().zip(self.ids.iter()) {
+            if lookup(table, spec)? != id {
+                return Err(ColumnSetError::Changed(spec.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The ID of the column at the given position in the set.
+    pub fn id(&self, index: usize) -> JET_COLUMNID {
+        self.ids[index]
+    }
+
+    pub fn ids(&self) -> &[JET_COLUMNID] {
+        &self.ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Make a request for each column in the set, for `JetTable::retrieve_columns`.
+    pub fn requests(&self) -> Vec<ColumnRequest> {
+        self.ids.iter().map(|&id| ColumnRequest::new(id, 0)).collect()
+    }
+}
+
+fn lookup(table: &JetTable, spec: &ColumnSpec) -> Result<JET_COLUMNID, ColumnSetError> {
+    let def = match table.get_column_def(&spec.name) {
+        Ok(def) => def,
+        Err(ref e) if e.code == JET_errColumnNotFound => {
+            return Err(ColumnSetError::Missing(spec.name.clone()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !spec.types.is_empty() && !spec.types.iter().any(|t| t.coltyp() == def.coltyp) {
+        return Err(ColumnSetError::WrongType {
+            column: spec.name.clone(),
+            expected: spec.types,
+            actual: def.coltyp,
+        });
+    }
+    if let Some(codepage) = spec.codepage {
+        let is_text = def.coltyp == JET_coltypText || def.coltyp == JET_coltypLongText;
+        if is_text && def.cp != codepage {
+            return Err(ColumnSetError::WrongCodePage {
+                column: spec.name.clone(),
+                expected: codepage,
+                actual: def.cp,
+            });
+        }
+    }
+    Ok(def.columnid)
+}
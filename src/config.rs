@@ -0,0 +1,146 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+
+use std::path::{Path, PathBuf};
+
+/// Typed settings for the most commonly used system parameters, applied to an instance with
+/// `JetInstance::configure`. Anything left as `None` keeps the engine's default.
+///
+/// Paths given for the system, log and temp directories have a trailing separator added, as the
+/// engine requires.
+#[derive(Debug, Default, Clone)]
+pub struct InstanceConfig {
+    pub page_size: Option<u32>,
+    /// Maximum size of the database cache, in pages.
+    pub cache_size_max: Option<u32>,
+    /// Size of each transaction log file, in KiB.
+    pub log_file_size: Option<u32>,
+    pub circular_log: Option<bool>,
+    pub recovery: Option<bool>,
+    pub system_path: Option<PathBuf>,
+    pub log_file_path: Option<PathBuf>,
+    pub temp_path: Option<PathBuf>,
+    pub max_sessions: Option<u32>,
+    pub max_open_tables: Option<u32>,
+    pub max_version_pages: Option<u32>,
+    /// The three-character prefix of the log and checkpoint files, like "edb".
+    pub base_name: Option<String>,
+}
+
+impl InstanceConfig {
+    pub fn new() -> InstanceConfig {
+        Self::default()
+    }
+
+    /// The database page size. This is global to the process, and can only be changed before the
+    /// first instance is initialized.
+    pub fn page_size(mut self, bytes: u32) -> Self {
+        self.page_size = Some(bytes);
+        self
+    }
+
+    pub fn cache_size_max(mut self, pages: u32) -> Self {
+        self.cache_size_max = Some(pages);
+        self
+    }
+
+    pub fn log_file_size(mut self, kib: u32) -> Self {
+        self.log_file_size = Some(kib);
+        self
+    }
+
+    pub fn circular_log(mut self, enabled: bool) -> Self {
+        self.circular_log = Some(enabled);
+        self
+    }
+
+    pub fn recovery(mut self, enabled: bool) -> Self {
+        self.recovery = Some(enabled);
+        self
+    }
+
+    pub fn system_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.system_path = Some(path.as_ref().to_owned());
+        self
+    }
+
+    pub fn log_file_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.log_file_path = Some(path.as_ref().to_owned());
+        self
+    }
+
+    pub fn temp_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.temp_path = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Set the system, log and temp paths all to the same directory.
+    pub fn all_paths<P: AsRef<Path>>(self, path: P) -> Self {
+        let path = path.as_ref();
+        self.system_path(path).log_file_path(path).temp_path(path)
+    }
+
+    pub fn max_sessions(mut self, n: u32) -> Self {
+        self.max_sessions = Some(n);
+        self
+    }
+
+    pub fn max_open_tables(mut self, n: u32) -> Self {
+        self.max_open_tables = Some(n);
+        self
+    }
+
+    pub fn max_version_pages(mut self, n: u32) -> Self {
+        self.max_version_pages = Some(n);
+        self
+    }
+
+    pub fn base_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.base_name = Some(name.into());
+        self
+    }
+
+    /// Set the parameters on an instance. See `JetInstance::configure` for when each kind can be
+    /// set.
+    pub(crate) fn apply(&self, instance: &mut JetInstance) -> Result<(), JetError> {
+        fn int(instance: &mut JetInstance, param: u32, value: Option<u32>)
+                -> Result<(), JetError> {
+            match value {
+                Some(value) => instance.set_int_parameter(param, value as usize),
+                None => Ok(()),
+            }
+        }
+        fn dir(instance: &mut JetInstance, param: u32, value: &Option<PathBuf>)
+                -> Result<(), JetError> {
+            match *value {
+                Some(ref path) => {
+                    let wpath: WideString = path.join("").as_os_str().into();
+                    instance.set_string_parameter(param, &wpath)
+                }
+                None => Ok(()),
+            }
+        }
+
+        int(instance, JET_paramDatabasePageSize, self.page_size)?;
+        int(instance, JET_paramCacheSizeMax, self.cache_size_max)?;
+        int(instance, JET_paramLogFileSize, self.log_file_size)?;
+        int(instance, JET_paramCircularLog, self.circular_log.map(u32::from))?;
+        if let Some(recovery) = self.recovery {
+            let value = if recovery { "On" } else { "Off" };
+            instance.set_string_parameter(JET_paramRecovery, &value.into())?;
+        }
+        dir(instance, JET_paramSystemPath, &self.system_path)?;
+        dir(instance, JET_paramLogFilePath, &self.log_file_path)?;
+        dir(instance, JET_paramTempPath, &self.temp_path)?;
+        int(instance, JET_paramMaxSessions, self.max_sessions)?;
+        int(instance, JET_paramMaxOpenTables, self.max_open_tables)?;
+        int(instance, JET_paramMaxVerPages, self.max_version_pages)?;
+        if let Some(ref name) = self.base_name {
+            instance.set_string_parameter(JET_paramBaseName, &name.as_str().into())?;
+        }
+        Ok(())
+    }
+}
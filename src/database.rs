@@ -23,6 +23,12 @@ impl<'a> JetDatabase<'a> {
         }
     }
 
+    /// Begin a transaction on the session this database was opened with. See
+    /// `JetSession::begin_transaction`.
+    pub fn begin_transaction<'b>(&'b self) -> Result<Transaction<'b>, JetError> {
+        self.session.begin_transaction()
+    }
+
     pub fn open_table<'b>(&'b self, tablename: &WideString) -> Result<JetTable<'b>, JetError> {
         debug!("opening JetTable {:?}", tablename);
         let mut tableid = JET_tableidNil;
@@ -31,6 +37,40 @@ impl<'a> JetDatabase<'a> {
         debug!("opened JetTable {:?} = {:x}", tablename, tableid);
         Ok(JetTable::new(self.session, self, tableid))
     }
+
+    /// Create a new table with the given columns and indexes, and open it.
+    ///
+    /// The table is built inside a transaction, so if any column or index can't be created, none
+    /// of it is left behind in the database.
+    pub fn create_table<'b>(&'b self, def: &TableDef) -> Result<JetTable<'b>, JetError> {
+        debug!("creating JetTable {:?}", def.name);
+        let transaction = self.session.begin_transaction()?;
+        let mut tableid = JET_tableidNil;
+        unsafe {
+            jettry!(JetCreateTableW(self.sesid, self.dbid, def.name.as_ptr(),
+                    def.initial_pages, def.density, &mut tableid));
+        }
+        debug!("created JetTable {:?} = {:x}", def.name, tableid);
+        {
+            // Rolling back the transaction invalidates this cursor, so it has to be closed before
+            // the transaction is finished either way.
+            let table = JetTable::new(self.session, self, tableid);
+            for column in &def.columns {
+                table.add_column(column)?;
+            }
+            for index in &def.indexes {
+                table.create_index(index)?;
+            }
+        }
+        transaction.commit(CommitMode::Durable)?;
+        self.open_table(&def.name)
+    }
+
+    pub fn delete_table(&self, tablename: &WideString) -> Result<(), JetError> {
+        debug!("deleting JetTable {:?}", tablename);
+        unsafe { jettry!(JetDeleteTableW(self.sesid, self.dbid, tablename.as_ptr())); }
+        Ok(())
+    }
 }
 
 impl<'a> Drop for JetDatabase<'a> {
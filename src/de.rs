@@ -0,0 +1,389 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+use super::util::decode_text;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess,
+    Visitor};
+use serde::de::value::SeqDeserializer;
+use serde::Deserialize;
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// An error from deserializing a record.
+#[derive(Debug)]
+pub enum DeError {
+    Jet(JetError),
+    Message(String),
+}
+
+impl From<JetError> for DeError {
+    fn from(e: JetError) -> DeError {
+        DeError::Jet(e)
+    }
+}
+
+impl Display for DeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            DeError::Jet(ref e) => Display::fmt(e, f),
+            DeError::Message(ref msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl Error for DeError {
+    fn description(&self) -> &str {
+        "ESE DB deserialization error"
+    }
+}
+
+impl de::Error for DeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DeError::Message(msg.to_string())
+    }
+}
+
+/// Deserialize the current record of a table into any type, matching fields to columns by name.
+///
+/// This lists the table's columns every time; to deserialize many records, make a
+/// `RecordDeserializer` once and use `RecordDeserializer::deserialize_record` instead.
+pub fn from_record<T: DeserializeOwned>(table: &JetTable) -> Result<T, DeError> {
+    RecordDeserializer::new(table)?.deserialize_record()
+}
+
+/// A serde `Deserializer` over the current record of a table. It gives the record as a map from
+/// column name to value, so it can fill in structs, maps or generic values like
+/// `serde_json::Value`.
+///
+/// Column values are given as:
+///
+///   - NULL: none / unit
+///   - integer, floating point and bit columns: the corresponding Rust primitive
+///   - text: a string (multi-valued columns give only their first value)
+///   - binary: a sequence of bytes, or a byte buffer if bytes are asked for
+///   - GUID: a string like "01234567-89ab-cdef-0123-456789abcdef", or 16 bytes if bytes are asked
+///     for
+///   - date/time: an ISO 8601 string like "2019-01-31T12:34:56.789", or the raw OLE Automation date
+///     if an f64 is asked for or the date is out of range
+#[derive(Debug)]
+pub struct RecordDeserializer<'a> {
+    table: &'a JetTable<'a>,
+    columns: Vec<(String, ColumnInfo)>,
+}
+
+impl<'a> RecordDeserializer<'a> {
+    /// Make a deserializer for a table. The table's columns are listed once here, and the
+    /// deserializer can be used for every record the table moves to.
+    pub fn new(table: &'a JetTable<'a>) -> Result<RecordDeserializer<'a>, JetError> {
+        let columns = table.list_columns()?
+            .into_iter()
+            .map(|info| (info.name.to_string_lossy(), info))
+            .collect();
+        Ok(RecordDeserializer {
+            table,
+            columns,
+        })
+    }
+
+    /// Deserialize the current record.
+    pub fn deserialize_record<T: DeserializeOwned>(&self) -> Result<T, DeError> {
+        T::deserialize(self)
+    }
+
+    fn map<'b>(&'b self, fields: Option<&'static [&'static str]>) -> RecordMap<'a, 'b> {
+        RecordMap {
+            table: self.table,
+            columns: self.columns.iter(),
+            fields,
+            current: None,
+        }
+    }
+}
+
+impl<'de, 'a, 'b> de::Deserializer<'de> for &'b RecordDeserializer<'a> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_map(self.map(None))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str,
+            fields: &'static [&'static str], visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_map(self.map(Some(fields)))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+struct RecordMap<'a, 'b> {
+    table: &'b JetTable<'a>,
+    columns: ::std::slice::Iter<'b, (String, ColumnInfo)>,
+    fields: Option<&'static [&'static str]>,
+    current: Option<&'b ColumnInfo>,
+}
+
+impl<'de, 'a, 'b> MapAccess<'de> for RecordMap<'a, 'b> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K)
+            -> Result<Option<K::Value>, DeError> {
+        loop {
+            let (name, info) = match self.columns.next() {
+                Some(entry) => (&entry.0, &entry.1),
+                None => return Ok(None),
+            };
+            // When filling a struct, skip the columns it doesn't have fields for.
+            if let Some(fields) = self.fields {
+                if !fields.contains(&name.as_str()) {
+                    continue;
+                }
+            }
+            self.current = Some(info);
+            return seed.deserialize(name.as_str().into_deserializer()).map(Some);
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let info = self.current.take().expect("next_value_seed called before next_key_seed");
+        let data = self.table.retrieve_column_bytes_opt::<u8>(info.column_id)?;
+        seed.deserialize(ColumnDeserializer { info, data })
+    }
+}
+
+/// Deserializes the value of one column.
+struct ColumnDeserializer<'b> {
+    info: &'b ColumnInfo,
+    data: Option<Vec<u8>>,
+}
+
+impl<'b> ColumnDeserializer<'b> {
+    fn fixed<T: Copy>(data: &[u8]) -> Result<T, DeError> {
+        if data.len() != ::std::mem::size_of::<T>() {
+            return Err(DeError::Message(format!(
+                "column value is {} bytes, expected {}", data.len(), ::std::mem::size_of::<T>())));
+        }
+        Ok(unsafe { (data.as_ptr() as *const T).read_unaligned() })
+    }
+}
+
+impl<'de, 'b> de::Deserializer<'de> for ColumnDeserializer<'b> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let data = match self.data {
+            Some(data) => data,
+            None => return visitor.visit_unit(),
+        };
+        match self.info.coltyp {
+            JET_coltypBit => visitor.visit_bool(Self::fixed::<u8>(&data)? != 0),
+            JET_coltypUnsignedByte => visitor.visit_u8(Self::fixed(&data)?),
+            JET_coltypShort => visitor.visit_i16(Self::fixed(&data)?),
+            JET_coltypUnsignedShort => visitor.visit_u16(Self::fixed(&data)?),
+            JET_coltypLong => visitor.visit_i32(Self::fixed(&data)?),
+            JET_coltypUnsignedLong => visitor.visit_u32(Self::fixed(&data)?),
+            JET_coltypLongLong | JET_coltypCurrency => visitor.visit_i64(Self::fixed(&data)?),
+            JET_coltypIEEESingle => visitor.visit_f32(Self::fixed(&data)?),
+            JET_coltypIEEEDouble => visitor.visit_f64(Self::fixed(&data)?),
+            JET_coltypDateTime => {
+                let date = Self::fixed(&data)?;
+                match ole_date_to_iso8601(date) {
+                    Some(s) => visitor.visit_string(s),
+                    // Out of range, or corrupt; give the raw number rather than a bogus date.
+                    None => visitor.visit_f64(date),
+                }
+            }
+            JET_coltypGUID => visitor.visit_string(guid_to_string(&Self::fixed(&data)?)),
+            JET_coltypText | JET_coltypLongText => {
+                visitor.visit_string(decode_text(&data, self.info.codepage))
+            }
+            // A sequence of bytes, rather than visit_byte_buf, so self-describing formats like
+            // serde_json::Value can hold it. Asking for bytes gets them as-is.
+            _ => visitor.visit_seq(SeqDeserializer::<_, DeError>::new(data.into_iter())),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.data.is_none() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.data {
+            Some(ref data) if self.info.coltyp == JET_coltypDateTime => {
+                visitor.visit_f64(Self::fixed(data)?)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.data {
+            Some(data) => visitor.visit_byte_buf(data),
+            None => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V)
+            -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 char str string unit unit_struct seq
+        tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Format a GUID stored in Windows' mixed-endian layout.
+fn guid_to_string(g: &[u8; 16]) -> String {
+    format!("{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-\
+            {:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        g[3], g[2], g[1], g[0], g[5], g[4], g[7], g[6],
+        g[8], g[9], g[10], g[11], g[12], g[13], g[14], g[15])
+}
+
+/// Format an OLE Automation date (days since 1899-12-30) as an ISO 8601 date and time, or `None`
+/// if it's outside the range OLE dates can represent (the years 100 to 9999).
+fn ole_date_to_iso8601(date: f64) -> Option<String> {
+    const MS_PER_DAY: i64 = 86_400_000;
+    // Also false for NaN.
+    if !(date > -657_435.0 && date < 2_958_466.0) {
+        return None;
+    }
+    // OLE dates before the epoch count the day backwards but the time of day forwards.
+    let day = date.trunc();
+    let time = (date - day).abs();
+    let ms = day as i64 * MS_PER_DAY + (time * MS_PER_DAY as f64).round() as i64;
+    let days = ms.div_euclid(MS_PER_DAY);
+    let ms_of_day = ms.rem_euclid(MS_PER_DAY);
+
+    // Convert days since 1899-12-30 to a civil date, using the algorithm from
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days - 25569 + 719_468; // shift the epoch to 0000-03-01
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+
+    Some(format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}", y, m, d,
+        ms_of_day / 3_600_000, ms_of_day / 60_000 % 60, ms_of_day / 1000 % 60, ms_of_day % 1000))
+}
+
+/// Any single column value, for deserializing records of unknown schema, such as into a
+/// `HashMap<String, ColumnValue>`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    /// Text, and also GUIDs and dates formatted as strings.
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl<'de> Deserialize<'de> for ColumnValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = ColumnValue;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                f.write_str("a column value")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Null)
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Null)
+            }
+
+            fn visit_some<D: de::Deserializer<'de>>(self, d: D) -> Result<ColumnValue, D::Error> {
+                ColumnValue::deserialize(d)
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Bool(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Int(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::UInt(v))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Float(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Text(v.to_owned()))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Text(v))
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Binary(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<ColumnValue, E> {
+                Ok(ColumnValue::Binary(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ColumnValue, A::Error> {
+                let mut data = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(b) = seq.next_element::<u8>()? {
+                    data.push(b);
+                }
+                Ok(ColumnValue::Binary(data))
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[test]
+fn test_ole_date() {
+    assert_eq!(Some("1899-12-30T00:00:00.000".to_owned()), ole_date_to_iso8601(0.));
+    assert_eq!(Some("1970-01-01T00:00:00.000".to_owned()), ole_date_to_iso8601(25569.));
+    assert_eq!(Some("2000-02-29T18:00:00.000".to_owned()), ole_date_to_iso8601(36585.75));
+    assert_eq!(Some("1899-12-29T06:00:00.000".to_owned()), ole_date_to_iso8601(-1.25));
+    assert_eq!(Some("9999-12-31T00:00:00.000".to_owned()), ole_date_to_iso8601(2_958_465.));
+    assert_eq!(None, ole_date_to_iso8601(1e300));
+    assert_eq!(None, ole_date_to_iso8601(-657_435.));
+    assert_eq!(None, ole_date_to_iso8601(::std::f64::NAN));
+}
+
+#[test]
+fn test_guid_to_string() {
+    let g = [0x67, 0x45, 0x23, 0x01, 0xab, 0x89, 0xef, 0xcd,
+             0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+    assert_eq!("01234567-89ab-cdef-0123-456789abcdef", guid_to_string(&g));
+}
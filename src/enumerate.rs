@@ -0,0 +1,139 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::ctypes::{c_ulong, c_void};
+use winapi::um::esent::*;
+
+use std::alloc::{alloc, dealloc, realloc, Layout};
+use std::mem::size_of;
+use std::ptr::null_mut;
+use std::slice;
+
+/// Options for `JetTable::enumerate_columns`.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct EnumerateOptions {
+    /// Don't return columns which are set to their default value.
+    pub ignore_default: bool,
+
+    /// Truncate values longer than this many bytes.
+    pub max_value_size: Option<u32>,
+}
+
+/// A column value returned by `JetTable::enumerate_columns`.
+#[derive(Debug, Clone)]
+pub struct EnumeratedColumn {
+    pub column_id: JET_COLUMNID,
+    /// The 1-based index of this value within a multi-valued column.
+    pub itag_sequence: u32,
+    pub value: Vec<u8>,
+    /// True if the value was cut short by `EnumerateOptions::max_value_size`.
+    pub truncated: bool,
+}
+
+// Mirrors of JET_ENUMCOLUMN and JET_ENUMCOLUMNVALUE. Without JET_bitEnumerateCompressOutput, the
+// union in JET_ENUMCOLUMN is always the (cEnumColumnValue, rgEnumColumnValue) variant.
+
+#[repr(C)]
+struct RawEnumColumn {
+    columnid: JET_COLUMNID,
+    err: JET_ERR,
+    cvalues: c_ulong,
+    values: *mut RawEnumColumnValue,
+}
+
+#[repr(C)]
+struct RawEnumColumnValue {
+    itag_sequence: c_ulong,
+    err: JET_ERR,
+    cb_data: c_ulong,
+    pv_data: *mut c_void,
+}
+
+// Every allocation made by the callback is prefixed with its size, so it can be freed and
+// reallocated later without the engine telling us the old size.
+const HEADER: usize = 16;
+
+fn layout(size: usize) -> Layout {
+    Layout::from_size_align(size + HEADER, HEADER).unwrap()
+}
+
+/// A JET_PFNREALLOC implementation using the Rust global allocator.
+unsafe extern "system" fn enum_realloc(_context: *mut c_void, pv: *mut c_void, cb: c_ulong)
+        -> *mut c_void {
+    let cb = cb as usize;
+    let base = if pv.is_null() {
+        null_mut()
+    } else {
+        (pv as *mut u8).sub(HEADER)
+    };
+    let new_base = if base.is_null() {
+        if cb == 0 {
+            return null_mut();
+        }
+        alloc(layout(cb))
+    } else {
+        let old_size = *(base as *const usize);
+        if cb == 0 {
+            dealloc(base, layout(old_size));
+            return null_mut();
+        }
+        realloc(base, layout(old_size), cb + HEADER)
+    };
+    if new_base.is_null() {
+        return null_mut();
+    }
+    *(new_base as *mut usize) = cb;
+    new_base.add(HEADER) as *mut c_void
+}
+
+pub(crate) fn realloc_fn() -> JET_PFNREALLOC {
+    Some(enum_realloc)
+}
+
+pub(crate) fn enumerate_grbit(options: &EnumerateOptions) -> JET_GRBIT {
+    if options.ignore_default {
+        JET_bitEnumerateIgnoreDefault
+    } else {
+        JET_bitNil
+    }
+}
+
+/// Copy the results of JetEnumerateColumns out of the engine-allocated array, then free it.
+///
+/// # Safety
+/// `columns` must be the array returned by JetEnumerateColumns using `realloc_fn()`, holding
+/// `count` entries.
+pub(crate) unsafe fn collect_enumerated(count: c_ulong, columns: *mut JET_ENUMCOLUMN)
+        -> Vec<EnumeratedColumn> {
+    debug_assert_eq!(size_of::<RawEnumColumn>(), size_of::<JET_ENUMCOLUMN>());
+    let mut out = vec![];
+    if columns.is_null() {
+        return out;
+    }
+    let raw_columns = slice::from_raw_parts(columns as *const RawEnumColumn, count as usize);
+    for column in raw_columns {
+        if column.err == JET_errSuccess && !column.values.is_null() {
+            let values = slice::from_raw_parts(column.values, column.cvalues as usize);
+            for value in values {
+                if value.err == JET_errSuccess || value.err == JET_wrnColumnTruncated {
+                    let data = if value.pv_data.is_null() {
+                        vec![]
+                    } else {
+                        slice::from_raw_parts(value.pv_data as *const u8, value.cb_data as usize)
+                            .to_vec()
+                    };
+                    out.push(EnumeratedColumn {
+                        column_id: column.columnid,
+                        itag_sequence: value.itag_sequence,
+                        value: data,
+                        truncated: value.err == JET_wrnColumnTruncated,
+                    });
+                }
+                enum_realloc(null_mut(), value.pv_data, 0);
+            }
+            enum_realloc(null_mut(), column.values as *mut c_void, 0);
+        }
+    }
+    enum_realloc(null_mut(), columns as *mut c_void, 0);
+    out
+}
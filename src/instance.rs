@@ -6,6 +6,10 @@ use super::*;
 
 use std::ptr::null;
 
+/// An instance of the database engine.
+///
+/// Once initialized, an instance can be shared between threads (for example in an `Arc`), and each
+/// thread can create its own sessions from it.
 #[derive(Debug)]
 pub struct JetInstance {
     instance: JET_INSTANCE,
@@ -39,6 +43,16 @@ impl JetInstance {
         Ok(())
     }
 
+    /// Set system parameters from a typed configuration.
+    ///
+    /// Global parameters (the page size) apply to the whole process and must be set before the
+    /// first instance in the process is initialized with `init`; they can be set before
+    /// `init_engine`. Instance parameters (everything else) need `init_engine` to have been called
+    /// first, and must be set before `init`.
+    pub fn configure(&mut self, config: &InstanceConfig) -> Result<(), JetError> {
+        config.apply(self)
+    }
+
     pub fn init(&mut self) -> Result<(), JetError> {
         debug!("initializing JetInstance");
         let mut instance = self.instance;
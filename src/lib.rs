@@ -6,6 +6,12 @@
 #[macro_use] extern crate log;
 
 extern crate winapi;
+#[cfg(feature = "async")] extern crate futures;
+#[cfg(feature = "serde")] extern crate serde;
+#[cfg(feature = "derive")] extern crate esedb_derive;
+#[cfg(feature = "derive")] pub use esedb_derive::EseRecord;
+// Lets the derive macro's `::esedb::` paths work in this crate's own tests.
+#[cfg(all(test, feature = "derive"))] extern crate self as esedb;
 //extern crate esent;
 
 // re-export the constants and types for esent-sys
@@ -13,15 +19,60 @@ pub use winapi::um::esent::*;
 
 #[macro_use] mod macros;
 
+#[cfg(feature = "async")] mod async_session;
+#[cfg(feature = "async")] pub use async_session::*;
+
+mod bookmark;
+pub use bookmark::*;
+
+mod column_set;
+pub use column_set::*;
+
+mod config;
+pub use config::*;
+
 mod database;
 pub use database::*;
 
+mod enumerate;
+pub use enumerate::*;
+
+#[cfg(feature = "serde")] mod de;
+#[cfg(feature = "serde")] pub use de::*;
+
 mod error;
 pub use error::*;
 
 mod instance;
 pub use instance::*;
 
+mod long_value;
+pub use long_value::*;
+
+mod mapping;
+pub use mapping::*;
+
+mod open;
+pub use open::*;
+
+mod owned;
+pub use owned::*;
+
+mod parallel;
+pub use parallel::*;
+
+mod pool;
+pub use pool::*;
+
+mod record;
+pub use record::*;
+
+mod retry;
+pub use retry::*;
+
+mod schema;
+pub use schema::*;
+
 mod session;
 pub use session::*;
 
@@ -31,6 +82,9 @@ pub use wide_string::*;
 mod table;
 pub use table::*;
 
+mod transaction;
+pub use transaction::*;
+
 mod util;
 
 #[cfg(test)]
@@ -47,4 +101,13 @@ mod test {
         assert_eq!(-1104, e.as_ref().err().unwrap().code);
         assert_eq!("Invalid session handle", e.as_ref().err().unwrap().text);
     }
+
+    #[test]
+    fn test_thread_safety() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        fn assert_send<T: Send>() {}
+        assert_send_sync::<JetInstance>();
+        assert_send_sync::<SessionPool>();
+        assert_send::<JetSession>();
+    }
 }
@@ -0,0 +1,159 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+fn io_error(e: JetError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+/// Streams the contents of a column of the current record, reading it a piece at a time using
+/// offsets into the long value.
+#[derive(Debug)]
+pub struct ColumnReader<'a> {
+    table: &'a JetTable<'a>,
+    column_id: JET_COLUMNID,
+    offset: u64,
+    size: Option<u64>,
+}
+
+impl<'a> ColumnReader<'a> {
+    pub fn new(table: &'a JetTable<'a>, column_id: JET_COLUMNID) -> ColumnReader<'a> {
+        ColumnReader {
+            table,
+            column_id,
+            offset: 0,
+            size: None,
+        }
+    }
+
+    /// The total size of the column in bytes.
+    pub fn len(&mut self) -> Result<u64, JetError> {
+        if let Some(size) = self.size {
+            return Ok(size);
+        }
+        let (_, size) = self.table.retrieve_column_at(self.column_id, 0, &mut [])?;
+        self.size = Some(size);
+        Ok(size)
+    }
+
+    pub fn is_empty(&mut self) -> Result<bool, JetError> {
+        self.len().map(|len| len == 0)
+    }
+}
+
+impl<'a> Read for ColumnReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(size) = self.size {
+            if self.offset >= size {
+                return Ok(0);
+            }
+        }
+        // Long values can't be bigger than 4 GB, so anything past that is the end.
+        let offset = match u32::try_from(self.offset) {
+            Ok(offset) => offset,
+            Err(_) => return Ok(0),
+        };
+        let (nread, size) = self.table.retrieve_column_at(self.column_id, offset, buf)
+            .map_err(io_error)?;
+        if nread != 0 || self.offset == 0 {
+            self.size = Some(size);
+        }
+        self.offset += nread as u64;
+        Ok(nread)
+    }
+}
+
+impl<'a> Seek for ColumnReader<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => checked_offset(self.offset, delta),
+            SeekFrom::End(delta) => checked_offset(self.len().map_err(io_error)?, delta),
+        };
+        match new_offset {
+            Some(offset) => {
+                self.offset = offset;
+                Ok(offset)
+            }
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput,
+                    "invalid seek to a negative offset")),
+        }
+    }
+}
+
+fn checked_offset(base: u64, delta: i64) -> Option<u64> {
+    if delta < 0 {
+        base.checked_sub(delta.wrapping_neg() as u64)
+    } else {
+        base.checked_add(delta as u64)
+    }
+}
+
+/// How a `ColumnWriter` puts data into the column.
+#[derive(Debug, Copy, Clone)]
+pub enum ColumnWriteMode {
+    /// Add to the end of the existing value.
+    Append,
+    /// Replace the existing value, starting at the given byte offset.
+    Overwrite(u64),
+}
+
+/// Streams data into a long value column of the record being updated.
+#[derive(Debug)]
+pub struct ColumnWriter<'a> {
+    table: &'a JetTable<'a>,
+    column_id: JET_COLUMNID,
+    offset: Option<u64>,
+}
+
+impl<'a> ColumnWriter<'a> {
+    pub fn new(table: &'a JetTable<'a>, column_id: JET_COLUMNID, mode: ColumnWriteMode)
+            -> ColumnWriter<'a> {
+        let offset = match mode {
+            ColumnWriteMode::Append => None,
+            ColumnWriteMode::Overwrite(offset) => Some(offset),
+        };
+        ColumnWriter {
+            table,
+            column_id,
+            offset,
+        }
+    }
+}
+
+impl<'a> Write for ColumnWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Setting a column to zero bytes without JET_bitSetZeroLength would make it NULL.
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let offset = match self.offset {
+            Some(offset) => Some(u32::try_from(offset).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput,
+                    "offset is beyond the largest possible long value")
+            })?),
+            None => None,
+        };
+        self.table.set_column_at(self.column_id, offset, buf).map_err(io_error)?;
+        if let Some(ref mut offset) = self.offset {
+            *offset += buf.len() as u64;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_checked_offset() {
+    assert_eq!(Some(5), checked_offset(10, -5));
+    assert_eq!(None, checked_offset(3, -5));
+    assert_eq!(Some(15), checked_offset(10, 5));
+}
@@ -0,0 +1,253 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+use super::util::*;
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::mem::size_of;
+
+/// A Rust struct which maps onto the columns of a table. This is normally implemented with
+/// `#[derive(EseRecord)]` from the `esedb-derive` crate (enabled with the "derive" feature).
+pub trait EseRecord: Sized {
+    /// The columns the struct's fields map onto, in field order.
+    fn column_specs() -> Vec<ColumnSpec>;
+
+    /// Resolve the struct's columns against a table, giving the set to pass to `read` and `write`.
+    fn resolve(table: &JetTable) -> Result<ColumnSet, ColumnSetError> {
+        ColumnSet::resolve(table, Self::column_specs())
+    }
+
+    /// Read the current record of the table. `columns` must come from `resolve`; a set with the
+    /// wrong number of columns fails with `JET_errInvalidParameter`.
+    fn read(table: &JetTable, columns: &ColumnSet) -> Result<Self, JetError>;
+
+    /// Set the columns of a record being inserted or replaced. `columns` must come from `resolve`.
+    fn write(&self, record: &RecordBuilder, columns: &ColumnSet) -> Result<(), JetError>;
+}
+
+/// Types which can be made from the raw bytes of a column value.
+pub trait FromColumn: Sized {
+    fn from_column(data: Vec<u8>) -> Result<Self, JetError>;
+}
+
+/// Types which can be stored as the raw bytes of a column value.
+pub trait ToColumn {
+    fn to_column(&self) -> Cow<[u8]>;
+}
+
+fn wrong_size() -> JetError {
+    let e = JetError::from(JET_errInvalidBufferSize);
+    error!("column value has the wrong size for its type: {}", e);
+    e
+}
+
+macro_rules! fixed_column {
+    ($($t:ty),+) => {
+        $(
+            impl FromColumn for $t {
+                fn from_column(data: Vec<u8>) -> Result<Self, JetError> {
+                    if data.len() != size_of::<$t>() {
+                        return Err(wrong_size());
+                    }
+                    Ok(unsafe { (data.as_ptr() as *const $t).read_unaligned() })
+                }
+            }
+
+            impl ToColumn for $t {
+                fn to_column(&self) -> Cow<[u8]> {
+                    Cow::Borrowed(byte_slice(self))
+                }
+            }
+        )+
+    }
+}
+
+fixed_column!(u8, i16, u16, i32, u32, i64, u64, f32, f64, [u8; 16]);
+
+impl FromColumn for bool {
+    fn from_column(data: Vec<u8>) -> Result<Self, JetError> {
+        u8::from_column(data).map(|b| b != 0)
+    }
+}
+
+impl ToColumn for bool {
+    fn to_column(&self) -> Cow<[u8]> {
+        Cow::Borrowed(if *self { &[0xFF] } else { &[0] })
+    }
+}
+
+impl FromColumn for Vec<u8> {
+    fn from_column(data: Vec<u8>) -> Result<Self, JetError> {
+        Ok(data)
+    }
+}
+
+impl ToColumn for Vec<u8> {
+    fn to_column(&self) -> Cow<[u8]> {
+        Cow::Borrowed(self)
+    }
+}
+
+// Strings are always read and written as UTF-16; `ColumnSet` only lets them map onto Unicode
+// text columns.
+
+impl FromColumn for WideString {
+    fn from_column(data: Vec<u8>) -> Result<Self, JetError> {
+        if data.len() % 2 != 0 {
+            return Err(wrong_size());
+        }
+        Ok(WideString::from(ucs2_from_bytes(&data)))
+    }
+}
+
+impl ToColumn for WideString {
+    fn to_column(&self) -> Cow<[u8]> {
+        // Includes the NUL terminator, the same as JetTable::update_wstr.
+        Cow::Borrowed(slice_transmute(self.as_ucs2_slice()))
+    }
+}
+
+impl FromColumn for String {
+    fn from_column(data: Vec<u8>) -> Result<Self, JetError> {
+        WideString::from_column(data).map(|s| s.to_string_lossy())
+    }
+}
+
+impl ToColumn for String {
+    fn to_column(&self) -> Cow<[u8]> {
+        Cow::Owned(WideString::from(self.as_str()).to_column().into_owned())
+    }
+}
+
+impl FromColumn for OsString {
+    fn from_column(data: Vec<u8>) -> Result<Self, JetError> {
+        WideString::from_column(data).map(|s| OsString::from(&s))
+    }
+}
+
+impl ToColumn for OsString {
+    fn to_column(&self) -> Cow<[u8]> {
+        Cow::Owned(WideString::from(self.as_os_str()).to_column().into_owned())
+    }
+}
+
+// These are used by the code generated by `#[derive(EseRecord)]`.
+
+/// Check that a column set has as many columns as the struct reading or writing it expects.
+pub fn check_column_count(columns: &ColumnSet, expected: usize) -> Result<(), JetError> {
+    if columns.len() != expected {
+        let e = JetError::from(JET_errInvalidParameter);
+        error!("column set has {} columns, expected {}: {}", columns.len(), expected, e);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Read a column which must not be NULL.
+pub fn read_column<T: FromColumn>(table: &JetTable, column_id: JET_COLUMNID)
+        -> Result<T, JetError> {
+    match table.retrieve_column_bytes_opt::<u8>(column_id)? {
+        Some(data) => T::from_column(data),
+        None => {
+            let e = JetError::from(JET_wrnColumnNull);
+            error!("column {} is NULL: {}", column_id, e);
+            Err(e)
+        }
+    }
+}
+
+/// Read a column, giving `None` if it is NULL.
+pub fn read_optional<T: FromColumn>(table: &JetTable, column_id: JET_COLUMNID)
+        -> Result<Option<T>, JetError> {
+    match table.retrieve_column_bytes_opt::<u8>(column_id)? {
+        Some(data) => T::from_column(data).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Read every value of a multi-valued column.
+pub fn read_multi<T: FromColumn>(table: &JetTable, column_id: JET_COLUMNID)
+        -> Result<Vec<T>, JetError> {
+    table.retrieve_multi_values(column_id)?.into_iter().map(T::from_column).collect()
+}
+
+pub fn write_column<T: ToColumn>(record: &RecordBuilder, column_id: JET_COLUMNID, value: &T)
+        -> Result<(), JetError> {
+    record.set_bytes(column_id, &value.to_column())
+}
+
+/// Write a column, setting it to NULL if the value is `None`.
+pub fn write_optional<T: ToColumn>(record: &RecordBuilder, column_id: JET_COLUMNID,
+        value: &Option<T>) -> Result<(), JetError> {
+    match *value {
+        Some(ref value) => write_column(record, column_id, value),
+        None => record.set_null(column_id),
+    }
+}
+
+/// Set a multi-valued column to exactly the given values, replacing any it already has.
+pub fn write_multi<T: ToColumn>(record: &RecordBuilder, column_id: JET_COLUMNID, values: &[T])
+        -> Result<(), JetError> {
+    record.clear_multi_values(column_id)?;
+    for value in values {
+        record.add_multi_value(column_id, &value.to_column())?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_column_conversions() {
+    assert_eq!(0x1234_5678u32, u32::from_column(vec![0x78, 0x56, 0x34, 0x12]).unwrap());
+    assert!(u32::from_column(vec![1, 2]).is_err());
+    assert_eq!(&[0x78, 0x56, 0x34, 0x12], &*0x1234_5678u32.to_column());
+}
+
+// Expands `#[derive(EseRecord)]` with every attribute, so the generated code is type-checked.
+#[cfg(all(test, feature = "derive"))]
+mod derive_test {
+    use super::*;
+
+    mod hex {
+        use super::*;
+
+        pub fn read(data: Option<Vec<u8>>) -> Result<String, JetError> {
+            Ok(data.unwrap_or_default().iter().map(|b| format!("{:02x}", b)).collect())
+        }
+
+        pub fn write(value: &str) -> Option<Vec<u8>> {
+            if value.is_empty() {
+                None
+            } else {
+                Some(value.as_bytes().to_vec())
+            }
+        }
+    }
+
+    #[derive(EseRecord)]
+    #[allow(dead_code)]
+    struct Everything {
+        id: u32,
+        #[ese(rename = "Title")]
+        title: String,
+        artist: Option<WideString>,
+        path: OsString,
+        #[ese(multi)]
+        tags: Vec<String>,
+        #[ese(with = "hex")]
+        hash: String,
+        #[ese(skip)]
+        cached: u64,
+    }
+
+    #[test]
+    fn test_derive_column_specs() {
+        let specs = Everything::column_specs();
+        let names = specs.iter().map(|s| s.name.to_string_lossy()).collect::<Vec<_>>();
+        assert_eq!(vec!["id", "Title", "artist", "path", "tags", "hash"], names);
+        assert_eq!(Some(1200), specs[2].codepage);
+        assert!(specs[5].types.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use winapi::ctypes::c_void;
+
+use super::*;
+
+use std::fs;
+use std::mem::size_of;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::ptr::null_mut;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static INSTANCE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// The number of `ReadOnlyDatabase`s open, which all share the process-wide page size. Holding the
+// lock also keeps two calls to `open_read_only` from setting the page size at the same time.
+static OPEN_DATABASES: Mutex<usize> = Mutex::new(0);
+
+/// Get the database page size the engine is currently set to use, which is global to the process.
+fn engine_page_size() -> Result<u32, JetError> {
+    let mut value: JET_API_PTR = 0;
+    unsafe {
+        jettry!(JetGetSystemParameterW(JET_instanceNil, JET_sesidNil, JET_paramDatabasePageSize,
+                &mut value, null_mut(), 0));
+    }
+    Ok(value as u32)
+}
+
+/// Read the page size of a database from its file header. This doesn't need an instance.
+pub fn get_database_page_size(path: &WideString) -> Result<u32, JetError> {
+    let mut page_size = 0u32;
+    unsafe {
+        jettry!(JetGetDatabaseFileInfoW(path.as_ptr(), &mut page_size as *mut u32 as *mut c_void,
+                size_of::<u32>() as u32, JET_DbInfoPageSize));
+    }
+    Ok(page_size)
+}
+
+/// A database opened by `open_read_only`. It derefs to an `OwnedDatabase`, and removes the
+/// temporary directory the engine used once it's dropped.
+#[derive(Debug)]
+pub struct ReadOnlyDatabase {
+    database: Option<OwnedDatabase>,
+    temp_dir: PathBuf,
+}
+
+impl Deref for ReadOnlyDatabase {
+    type Target = OwnedDatabase;
+    fn deref(&self) -> &OwnedDatabase {
+        self.database.as_ref().unwrap()
+    }
+}
+
+impl Drop for ReadOnlyDatabase {
+    fn drop(&mut self) {
+        // Close the database, session and instance first, so the engine's files aren't in use.
+        self.database.take();
+        *OPEN_DATABASES.lock().unwrap() -= 1;
+        if let Err(e) = fs::remove_dir_all(&self.temp_dir) {
+            warn!("failed to remove temporary directory {:?}: {}", self.temp_dir, e);
+        }
+    }
+}
+
+/// Open a database file for read-only inspection, with all the setup done automatically.
+///
+/// The page size is read from the file header, and the engine's system, log and temp files are put
+/// in a new temporary directory, so nothing is written next to the database file.
+///
+/// The engine's page size is shared by the whole process, so every database open at the same time
+/// must have the same page size. Opening one with a different page size while an earlier
+/// `ReadOnlyDatabase` is still open fails with `JET_errPageSizeMismatch`, and the engine refuses to
+/// change it while any other instance in the process is running.
+pub fn open_read_only(path: &Path) -> Result<ReadOnlyDatabase, JetError> {
+    let wpath: WideString = path.as_os_str().into();
+    let page_size = get_database_page_size(&wpath)?;
+
+    let mut open_databases = OPEN_DATABASES.lock().unwrap();
+    let current_page_size = engine_page_size()?;
+    let set_page_size = if current_page_size == page_size {
+        None
+    } else if *open_databases > 0 {
+        let e = JetError::from(JET_errPageSizeMismatch);
+        error!("can't open {:?} with {}-byte pages while databases with {}-byte pages are open; \
+            the page size is global to the process: {}", path, page_size, current_page_size, e);
+        return Err(e);
+    } else {
+        Some(page_size)
+    };
+
+    let name = format!("esedb-rs-{}-{}", process::id(),
+        INSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst));
+    let temp_dir = std::env::temp_dir().join(&name);
+    if let Err(e) = fs::create_dir_all(&temp_dir) {
+        error!("failed to create temporary directory {:?}: {}", temp_dir, e);
+        return Err(JetError::from(JET_errInvalidPath));
+    }
+
+    match open_in(&wpath, set_page_size, &name, &temp_dir) {
+        Ok(database) => {
+            *open_databases += 1;
+            Ok(ReadOnlyDatabase {
+                database: Some(database),
+                temp_dir,
+            })
+        }
+        Err(e) => {
+            let _ = fs::remove_dir_all(&temp_dir);
+            Err(e)
+        }
+    }
+}
+
+fn open_in(path: &WideString, page_size: Option<u32>, name: &str, temp_dir: &Path)
+        -> Result<OwnedDatabase, JetError> {
+    let mut instance = JetInstance::new();
+    if let Some(page_size) = page_size {
+        instance.configure(&InstanceConfig::new().page_size(page_size))?;
+    }
+    instance.init_engine(&name.into())?;
+    instance.configure(&InstanceConfig::new()
+        .all_paths(temp_dir)
+        .recovery(false)
+        .circular_log(true))?;
+    instance.init()?;
+
+    let session = OwnedSession::new(Arc::new(instance))?;
+    session.open_database(path, DatabaseAccessMode::ReadOnly)
+}
@@ -0,0 +1,135 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+//! Reference-counted handles which own their parent objects, as an alternative to the borrowing
+//! `JetSession`, `JetDatabase` and `JetTable` types.
+//!
+//! Each handle keeps its parent alive, so they can be stored together in one struct or returned
+//! from functions, and they are always closed in the right order: a table before its database, a
+//! database before its session, and a session before its instance.
+
+use winapi::um::esent::*;
+use super::*;
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+// Each of these holds a borrowing wrapper whose lifetime is faked as 'static. This is sound
+// because the referent is kept alive (and at a fixed address) by the Arc field that follows it,
+// and fields are dropped in declaration order, so the wrapper is always dropped first. The
+// 'static lifetime is never exposed: Deref only hands out borrows tied to the handle itself.
+
+#[derive(Debug)]
+struct SessionHandle {
+    session: JetSession<'static>,
+    _instance: Arc<JetInstance>,
+}
+
+#[derive(Debug)]
+struct DatabaseHandle {
+    database: JetDatabase<'static>,
+    session: Arc<SessionHandle>,
+}
+
+/// A session which keeps its instance alive. Cloning it gives another reference to the same
+/// session.
+#[derive(Debug, Clone)]
+pub struct OwnedSession {
+    handle: Arc<SessionHandle>,
+}
+
+impl OwnedSession {
+    /// Begin a new session on an initialized instance.
+    pub fn new(instance: Arc<JetInstance>) -> Result<OwnedSession, JetError> {
+        let instance_ref: &'static JetInstance = unsafe { &*(&*instance as *const JetInstance) };
+        let session = instance_ref.create_session()?;
+        Ok(OwnedSession {
+            handle: Arc::new(SessionHandle {
+                session,
+                _instance: instance,
+            }),
+        })
+    }
+
+    fn session_ref(&self) -> &'static JetSession<'static> {
+        unsafe { &*(&self.handle.session as *const JetSession<'static>) }
+    }
+
+    /// Attach and open a database, keeping this session alive for as long as it's open.
+    pub fn open_database(&self, path: &WideString, mode: DatabaseAccessMode)
+            -> Result<OwnedDatabase, JetError> {
+        let dbid = self.handle.session.open_database_id(path, mode)?;
+        Ok(self.wrap_database(dbid))
+    }
+
+    /// Create a new database file and open it, keeping this session alive for as long as it's
+    /// open.
+    pub fn create_database(&self, path: &WideString) -> Result<OwnedDatabase, JetError> {
+        let dbid = self.handle.session.create_database_id(path)?;
+        Ok(self.wrap_database(dbid))
+    }
+
+    fn wrap_database(&self, dbid: JET_DBID) -> OwnedDatabase {
+        OwnedDatabase {
+            handle: Arc::new(DatabaseHandle {
+                database: JetDatabase::new(self.session_ref(), dbid),
+                session: Arc::clone(&self.handle),
+            }),
+        }
+    }
+}
+
+impl Deref for OwnedSession {
+    type Target = JetSession<'static>;
+    fn deref(&self) -> &Self::Target {
+        &self.handle.session
+    }
+}
+
+/// An open database which keeps its session alive. Cloning it gives another reference to the same
+/// database.
+#[derive(Debug, Clone)]
+pub struct OwnedDatabase {
+    handle: Arc<DatabaseHandle>,
+}
+
+impl OwnedDatabase {
+    /// Open a table, keeping this database alive for as long as it's open.
+    pub fn open_table(&self, tablename: &WideString) -> Result<OwnedTable, JetError> {
+        let database: &'static JetDatabase<'static> =
+            unsafe { &*(&self.handle.database as *const JetDatabase<'static>) };
+        let table = database.open_table(tablename)?;
+        Ok(OwnedTable {
+            table,
+            _database: Arc::clone(&self.handle),
+        })
+    }
+
+    /// The session this database was opened with.
+    pub fn session(&self) -> OwnedSession {
+        OwnedSession {
+            handle: Arc::clone(&self.handle.session),
+        }
+    }
+}
+
+impl Deref for OwnedDatabase {
+    type Target = JetDatabase<'static>;
+    fn deref(&self) -> &Self::Target {
+        &self.handle.database
+    }
+}
+
+/// An open table which keeps its database alive.
+#[derive(Debug)]
+pub struct OwnedTable {
+    table: JetTable<'static>,
+    _database: Arc<DatabaseHandle>,
+}
+
+impl Deref for OwnedTable {
+    type Target = JetTable<'static>;
+    fn deref(&self) -> &Self::Target {
+        &self.table
+    }
+}
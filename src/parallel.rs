@@ -0,0 +1,198 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+
+/// The order `parallel_scan` delivers results in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScanOrder {
+    /// As soon as they're ready, interleaved between partitions.
+    Unordered,
+    /// In primary key order. Results from later partitions are buffered in memory until all the
+    /// earlier ones have been delivered.
+    KeyOrder,
+}
+
+enum Message<T> {
+    Row(usize, Result<T, JetError>),
+    Done(usize),
+}
+
+/// Scan every record of a table using several threads at once, each with its own session from the
+/// pool, calling `f` on each record and passing its result to `sink` on the calling thread.
+///
+/// The primary index is split into `partitions` roughly equal ranges using the engine's estimate of
+/// record positions, and each range is scanned by its own thread.
+///
+/// If `f` panics, or any partition stops before reaching its end, the scan fails with
+/// `JET_errCallbackFailed` rather than returning with rows missing.
+pub fn parallel_scan<T, F, S>(pool: &SessionPool, path: &WideString, tablename: &WideString,
+        partitions: usize, order: ScanOrder, f: F, mut sink: S) -> Result<(), JetError>
+    where F: Fn(&JetTable) -> Result<T, JetError> + Send + Sync + 'static,
+          T: Send + 'static,
+          S: FnMut(T),
+{
+    let boundaries = {
+        let mut session = pool.get()?;
+        let table = session.table(path, DatabaseAccessMode::ReadOnly, tablename)?;
+        partition_boundaries(table, partitions.max(1))?
+    };
+    let partitions = boundaries.len() + 1;
+    debug!("scanning {:?} in {} partitions", tablename, partitions);
+
+    let boundaries = Arc::new(boundaries);
+    let f = Arc::new(f);
+    let (tx, rx) = mpsc::sync_channel(1024);
+    let mut threads = vec![];
+    for i in 0 .. partitions {
+        let pool = pool.clone();
+        let path = path.clone();
+        let tablename = tablename.clone();
+        let boundaries = Arc::clone(&boundaries);
+        let f = Arc::clone(&f);
+        let tx = tx.clone();
+        threads.push(thread::spawn(move || {
+            let start = if i == 0 { None } else { Some(&boundaries[i - 1]) };
+            let end = boundaries.get(i);
+            let result = scan_partition(&pool, &path, &tablename, start, end, &*f, |row| {
+                tx.send(Message::Row(i, row)).is_ok()
+            });
+            if let Err(e) = result {
+                let _ = tx.send(Message::Row(i, Err(e)));
+            }
+            let _ = tx.send(Message::Done(i));
+        }));
+    }
+    drop(tx);
+
+    let mut result = Ok(());
+    let mut current = 0;
+    let mut done = vec![false; partitions];
+    let mut buffered: Vec<VecDeque<T>> = (0 .. partitions).map(|_| VecDeque::new()).collect();
+    for message in rx.iter() {
+        match message {
+            Message::Row(_, Err(e)) => {
+                // Dropping the receiver makes the other threads stop.
+                result = Err(e);
+                break;
+            }
+            Message::Row(i, Ok(value)) => {
+                if order == ScanOrder::Unordered || i == current {
+                    sink(value);
+                } else {
+                    buffered[i].push_back(value);
+                }
+            }
+            Message::Done(i) => {
+                done[i] = true;
+                while order == ScanOrder::KeyOrder && current < partitions && done[current] {
+                    current += 1;
+                    if current < partitions {
+                        for value in buffered[current].drain(..) {
+                            sink(value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    drop(rx);
+
+    for thread in threads {
+        if thread.join().is_err() {
+            error!("parallel scan thread panicked");
+            if result.is_ok() {
+                result = Err(JetError::from(JET_errCallbackFailed));
+            }
+        }
+    }
+    // Every partition must have finished, or rows are missing.
+    if result.is_ok() && done.iter().any(|&d| !d) {
+        error!("parallel scan partition stopped without finishing");
+        result = Err(JetError::from(JET_errCallbackFailed));
+    }
+    result
+}
+
+/// Find the bookmarks which split the primary index into roughly equal parts. Partitions which
+/// would be empty are merged.
+fn partition_boundaries(table: &JetTable, partitions: usize) -> Result<Vec<Bookmark>, JetError> {
+    table.select_primary_index()?;
+    let mut boundaries: Vec<Bookmark> = vec![];
+    for i in 1 .. partitions {
+        match table.goto_position(i as f64 / partitions as f64) {
+            Ok(()) => (),
+            // empty table
+            Err(ref e) if e.code == JET_errNoCurrentRecord => break,
+            Err(e) => return Err(e),
+        }
+        boundaries.push(table.get_bookmark()?);
+    }
+    boundaries.sort();
+    boundaries.dedup();
+    Ok(boundaries)
+}
+
+/// Scan the records from `start` (inclusive, or the first record) up to `end` (exclusive, or the
+/// end of the table), stopping early if `emit` returns false.
+fn scan_partition<T, F, E>(pool: &SessionPool, path: &WideString, tablename: &WideString,
+        start: Option<&Bookmark>, end: Option<&Bookmark>, f: &F, mut emit: E)
+        -> Result<(), JetError>
+    where F: Fn(&JetTable) -> Result<T, JetError>,
+          E: FnMut(Result<T, JetError>) -> bool,
+{
+    let mut session = pool.get()?;
+    let table = session.table(path, DatabaseAccessMode::ReadOnly, tablename)?;
+    table.select_primary_index()?;
+    let positioned = match start {
+        Some(bookmark) => table.goto_bookmark(bookmark),
+        None => table.move_first(),
+    };
+    match positioned {
+        Ok(()) => (),
+        Err(ref e) if e.code == JET_errNoCurrentRecord => return Ok(()),
+        Err(e) => return Err(e),
+    }
+    if let Some(end) = end {
+        // The engine stops at the end of the range, so rows don't need to be compared with it.
+        if !table.set_range_end(end)? {
+            return Ok(());
+        }
+    }
+    let result = scan_rows(table, f, emit);
+    if end.is_some() {
+        // The table goes back to the pool with the session, so don't leave the range on it.
+        table.remove_index_range()?;
+    }
+    result
+}
+
+fn scan_rows<T, F, E>(table: &JetTable, f: &F, mut emit: E) -> Result<(), JetError>
+    where F: Fn(&JetTable) -> Result<T, JetError>,
+          E: FnMut(Result<T, JetError>) -> bool,
+{
+    loop {
+        let row = panic::catch_unwind(AssertUnwindSafe(|| f(table)))
+            .unwrap_or_else(|_| Err(row_panicked()));
+        if !emit(row) {
+            return Ok(());
+        }
+        match table.move_next() {
+            Ok(()) => (),
+            Err(ref e) if e.code == JET_errNoCurrentRecord => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn row_panicked() -> JetError {
+    error!("parallel scan callback panicked");
+    JetError::from(JET_errCallbackFailed)
+}
@@ -0,0 +1,195 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+struct PoolEntry {
+    // Fields are dropped in order: tables, then databases, then the session they all use.
+    tables: Vec<(usize, Vec<u16>, JetTable<'static>)>,
+    databases: Vec<(Vec<u16>, DatabaseAccessMode, JetDatabase<'static>)>,
+    session: Box<JetSession<'static>>,
+    _instance: Arc<JetInstance>,
+}
+
+// The databases and tables only refer to the session in the same entry, which is boxed so it
+// doesn't move. An entry is only ever used by one thread at a time: either it's idle in the pool,
+// or it's checked out in a PooledSession, which can't leave the thread that got it.
+unsafe impl Send for PoolEntry {}
+
+impl fmt::Debug for PoolEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PoolEntry({:?})", self.session)
+    }
+}
+
+impl PoolEntry {
+    fn new(instance: Arc<JetInstance>) -> Result<PoolEntry, JetError> {
+        let session = {
+            let instance_ref: &'static JetInstance =
+                unsafe { &*(&*instance as *const JetInstance) };
+            Box::new(instance_ref.create_session()?)
+        };
+        Ok(PoolEntry {
+            tables: vec![],
+            databases: vec![],
+            session,
+            _instance: instance,
+        })
+    }
+
+    fn session_ref(&self) -> &'static JetSession<'static> {
+        unsafe { &*(&*self.session as *const JetSession<'static>) }
+    }
+
+    fn database_index(&mut self, path: &WideString, mode: DatabaseAccessMode)
+            -> Result<usize, JetError> {
+        let key = path.as_ucs2_slice();
+        if let Some(i) = self.databases.iter().position(|&(ref k, _, _)| k.as_slice() == key) {
+            // A database opened for writing can be used for reading, but not the other way round.
+            if mode == DatabaseAccessMode::ReadWrite
+                    && self.databases[i].1 == DatabaseAccessMode::ReadOnly {
+                error!("database {:?} was already opened read-only by this session", path);
+                return Err(JetError::from(JET_errPermissionDenied));
+            }
+            return Ok(i);
+        }
+        let dbid = self.session.open_database_id(path, mode)?;
+        let database = JetDatabase::new(self.session_ref(), dbid);
+        self.databases.push((key.to_vec(), mode, database));
+        Ok(self.databases.len() - 1)
+    }
+}
+
+#[derive(Debug)]
+struct PoolShared {
+    instance: Arc<JetInstance>,
+    idle: Mutex<Vec<PoolEntry>>,
+    max_idle: usize,
+}
+
+/// A pool of sessions on one instance, for handing out to worker threads.
+///
+/// Each session keeps the databases and tables opened through it open while it's idle in the pool,
+/// so a thread that gets it later doesn't have to open them again. Cloning the pool gives another
+/// reference to the same pool.
+#[derive(Debug, Clone)]
+pub struct SessionPool {
+    shared: Arc<PoolShared>,
+}
+
+impl SessionPool {
+    /// Make a pool of sessions on an initialized instance, keeping at most `max_idle` sessions
+    /// around when they aren't in use.
+    pub fn new(instance: Arc<JetInstance>, max_idle: usize) -> SessionPool {
+        SessionPool {
+            shared: Arc::new(PoolShared {
+                instance,
+                idle: Mutex::new(vec![]),
+                max_idle,
+            }),
+        }
+    }
+
+    /// Get a session for use by the current thread, creating a new one if none are idle. It goes
+    /// back to the pool when dropped.
+    pub fn get(&self) -> Result<PooledSession, JetError> {
+        let idle = self.shared.idle.lock().unwrap().pop();
+        let entry = match idle {
+            Some(entry) => entry,
+            None => {
+                debug!("no idle sessions in pool; creating a new one");
+                PoolEntry::new(Arc::clone(&self.shared.instance))?
+            }
+        };
+        Ok(PooledSession {
+            entry: Some(entry),
+            pool: Arc::clone(&self.shared),
+            _not_send: PhantomData,
+        })
+    }
+
+    /// The number of sessions currently idle in the pool.
+    pub fn idle_count(&self) -> usize {
+        self.shared.idle.lock().unwrap().len()
+    }
+}
+
+/// A session checked out from a `SessionPool`. It can only be used on the thread that got it.
+///
+/// Sending one to another thread doesn't compile:
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<esedb::PooledSession>();
+/// ```
+#[derive(Debug)]
+pub struct PooledSession {
+    entry: Option<PoolEntry>,
+    pool: Arc<PoolShared>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl PooledSession {
+    fn entry(&mut self) -> &mut PoolEntry {
+        self.entry.as_mut().unwrap()
+    }
+
+    /// Get a database opened by this session, opening it if this session hasn't already.
+    ///
+    /// A database this session already opened read-only can't be got again for writing; that fails
+    /// with `JET_errPermissionDenied`. Asking for read-only access to one opened for writing gives
+    /// the writable handle.
+    pub fn database(&mut self, path: &WideString, mode: DatabaseAccessMode)
+            -> Result<&JetDatabase<'static>, JetError> {
+        let entry = self.entry();
+        let i = entry.database_index(path, mode)?;
+        Ok(&entry.databases[i].2)
+    }
+
+    /// Get a table opened by this session, opening it (and its database) if this session hasn't
+    /// already. The table's cursor is wherever it was left by the last use, so it should be
+    /// repositioned before use.
+    pub fn table(&mut self, path: &WideString, mode: DatabaseAccessMode, tablename: &WideString)
+            -> Result<&JetTable<'static>, JetError> {
+        let entry = self.entry();
+        let db = entry.database_index(path, mode)?;
+        let name = tablename.as_ucs2_slice();
+        let found = entry.tables.iter()
+            .position(|&(d, ref n, _)| d == db && n.as_slice() == name);
+        let i = match found {
+            Some(i) => i,
+            None => {
+                let database: &'static JetDatabase<'static> =
+                    unsafe { &*(&entry.databases[db].2 as *const JetDatabase<'static>) };
+                let table = database.open_table(tablename)?;
+                entry.tables.push((db, name.to_vec(), table));
+                entry.tables.len() - 1
+            }
+        };
+        Ok(&entry.tables[i].2)
+    }
+}
+
+impl Deref for PooledSession {
+    type Target = JetSession<'static>;
+    fn deref(&self) -> &Self::Target {
+        &self.entry.as_ref().unwrap().session
+    }
+}
+
+impl Drop for PooledSession {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            let mut idle = self.pool.idle.lock().unwrap();
+            if idle.len() < self.pool.max_idle {
+                idle.push(entry);
+            }
+        }
+    }
+}
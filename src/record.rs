@@ -0,0 +1,129 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use winapi::ctypes::c_void;
+
+use super::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum UpdateKind {
+    Insert,
+    Replace,
+}
+
+/// A pending insert of a new record or replacement of the current one, created with
+/// `JetTable::insert` or `JetTable::replace`.
+///
+/// Set columns on it and then call `save` to write it to the table. If it is dropped without being
+/// saved, the update is cancelled and no changes are made.
+#[derive(Debug)]
+pub struct RecordBuilder<'a> {
+    table: &'a JetTable<'a>,
+    kind: UpdateKind,
+    finished: bool,
+}
+
+impl<'a> RecordBuilder<'a> {
+    fn prepare(table: &'a JetTable<'a>, kind: UpdateKind) -> Result<RecordBuilder<'a>, JetError> {
+        let prep = match kind {
+            UpdateKind::Insert => JET_prepInsert,
+            UpdateKind::Replace => JET_prepReplace,
+        };
+        debug!("preparing {:?} on JetTable {:x}", kind, unsafe { table.raw() });
+        unsafe { jettry!(JetPrepareUpdate(table.raw_session(), table.raw(), prep)); }
+        Ok(RecordBuilder {
+            table,
+            kind,
+            finished: false,
+        })
+    }
+
+    pub(crate) fn insert(table: &'a JetTable<'a>) -> Result<RecordBuilder<'a>, JetError> {
+        Self::prepare(table, UpdateKind::Insert)
+    }
+
+    pub(crate) fn replace(table: &'a JetTable<'a>) -> Result<RecordBuilder<'a>, JetError> {
+        Self::prepare(table, UpdateKind::Replace)
+    }
+
+    pub fn set<T: Copy>(&self, column_id: JET_COLUMNID, data: &T) -> Result<(), JetError> {
+        self.table.update(column_id, data)
+    }
+
+    pub fn set_slice<T: Copy>(&self, column_id: JET_COLUMNID, slice: &[T])
+            -> Result<(), JetError> {
+        self.table.update_slice(column_id, slice)
+    }
+
+    pub fn set_wstr(&self, column_id: JET_COLUMNID, wstr: &WideString) -> Result<(), JetError> {
+        self.table.update_wstr(column_id, wstr)
+    }
+
+    pub fn set_null(&self, column_id: JET_COLUMNID) -> Result<(), JetError> {
+        self.table.update_null(column_id)
+    }
+
+    pub fn set_bytes(&self, column_id: JET_COLUMNID, data: &[u8]) -> Result<(), JetError> {
+        self.table.update_slice(column_id, data)
+    }
+
+    /// Add a new value to a multi-valued column. When replacing a record, this adds to the values
+    /// it already has; use `clear_multi_values` first to replace them.
+    pub fn add_multi_value(&self, column_id: JET_COLUMNID, data: &[u8]) -> Result<(), JetError> {
+        self.table.add_multi_value(column_id, data)
+    }
+
+    /// Remove every value of a multi-valued column.
+    pub fn clear_multi_values(&self, column_id: JET_COLUMNID) -> Result<(), JetError> {
+        self.table.clear_multi_values(column_id)
+    }
+
+    /// Get the value of a column as it will be saved. For an auto-increment column of a record
+    /// being inserted, this is the value the engine assigned to it.
+    pub fn retrieve<T: Copy>(&self, column_id: JET_COLUMNID) -> Result<T, JetError> {
+        self.table.retrieve_copy(column_id)
+    }
+
+    /// Get a writer for streaming data into a long value column of the record.
+    pub fn column_writer(&self, column_id: JET_COLUMNID, mode: ColumnWriteMode)
+            -> ColumnWriter<'a> {
+        self.table.column_writer(column_id, mode)
+    }
+
+    /// Write the record to the table, returning its bookmark.
+    pub fn save(mut self) -> Result<Bookmark, JetError> {
+        let mut data = vec![0u8; BOOKMARK_MAX];
+        let mut nbytes = 0u32;
+        unsafe {
+            jettry!(JetUpdate(self.table.raw_session(), self.table.raw(),
+                    data.as_mut_ptr() as *mut c_void, data.len() as u32, &mut nbytes));
+        }
+        self.finished = true;
+        debug!("saved {:?} on JetTable {:x}", self.kind, unsafe { self.table.raw() });
+        Ok(Bookmark::from(data[..nbytes as usize].to_vec()))
+    }
+
+    /// Discard the changes. This is the same as dropping the builder, except errors are returned.
+    pub fn cancel(mut self) -> Result<(), JetError> {
+        self.finished = true;
+        self.cancel_internal()
+    }
+
+    fn cancel_internal(&self) -> Result<(), JetError> {
+        debug!("cancelling {:?} on JetTable {:x}", self.kind, unsafe { self.table.raw() });
+        unsafe {
+            jetcall!(JetPrepareUpdate(self.table.raw_session(), self.table.raw(), JET_prepCancel))
+        }
+    }
+}
+
+impl<'a> Drop for RecordBuilder<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(e) = self.cancel_internal() {
+                error!("failed to cancel update: {}", e);
+            }
+        }
+    }
+}
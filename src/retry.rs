@@ -0,0 +1,132 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+
+use std::thread;
+use std::time::Duration;
+
+/// How `Retrier` re-runs transactions that fail because of contention.
+#[derive(Debug, Copy, Clone)]
+pub struct RetryPolicy {
+    /// Total number of times to try, including the first.
+    pub max_attempts: u32,
+    /// How long to wait before the first retry. This doubles after each further retry.
+    pub initial_backoff: Duration,
+    /// The longest to wait between retries.
+    pub max_backoff: Duration,
+    pub commit_mode: CommitMode,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            commit_mode: CommitMode::Durable,
+        }
+    }
+}
+
+/// Counts of what `Retrier` has done, accumulated over all the transactions it has run.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RetryStats {
+    /// Transactions that eventually committed.
+    pub committed: u64,
+    /// Transactions that failed, either with a non-retryable error or by running out of attempts.
+    pub failed: u64,
+    /// Attempts that were rolled back and retried.
+    pub retries: u64,
+    pub write_conflicts: u64,
+    pub version_store_exhausted: u64,
+    pub total_backoff: Duration,
+}
+
+/// Whether an error is caused by contention with other sessions, so that the transaction is
+/// likely to succeed if rolled back and tried again.
+pub fn is_transient_error(e: &JetError) -> bool {
+    match e.code {
+        JET_errWriteConflict
+            | JET_errWriteConflictPrimaryIndex
+            | JET_errVersionStoreOutOfMemory
+            | JET_errVersionStoreOutOfMemoryAndCleanupTimedOut => true,
+        _ => false,
+    }
+}
+
+/// Runs transactions, retrying them with backoff when they fail due to write conflicts or the
+/// version store being exhausted.
+#[derive(Debug, Default)]
+pub struct Retrier {
+    policy: RetryPolicy,
+    stats: RetryStats,
+}
+
+impl Retrier {
+    pub fn new(policy: RetryPolicy) -> Retrier {
+        Retrier {
+            policy,
+            stats: RetryStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> &RetryStats {
+        &self.stats
+    }
+
+    /// Run `f` inside a transaction on the given session and commit it. If `f` or the commit fails
+    /// with a transient error, the transaction is rolled back and `f` is run again in a new one.
+    ///
+    /// Because `f` may run more than once, it should not have side effects outside the database.
+    ///
+    /// This must not be called while a transaction is already open on the session; that fails
+    /// with `JET_errInTransaction` without running `f`. A nested transaction sees the outer one's
+    /// snapshot, so a conflict with a change committed since then would happen again on every
+    /// retry.
+    pub fn run<T, F>(&mut self, session: &JetSession, mut f: F) -> Result<T, JetError>
+        where F: FnMut(&mut Transaction) -> Result<T, JetError>
+    {
+        if session.transaction_depth() != 0 {
+            let e = JetError::from(JET_errInTransaction);
+            error!("can't retry transactions inside another transaction: {}", e);
+            return Err(e);
+        }
+        let mut backoff = self.policy.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let result = session.begin_transaction().and_then(|mut txn| {
+                let value = f(&mut txn)?;
+                txn.commit(self.policy.commit_mode)?;
+                Ok(value)
+            });
+
+            let e = match result {
+                Ok(value) => {
+                    self.stats.committed += 1;
+                    return Ok(value);
+                }
+                Err(e) => e,
+            };
+
+            if !is_transient_error(&e) || attempt >= self.policy.max_attempts {
+                self.stats.failed += 1;
+                return Err(e);
+            }
+
+            if e.code == JET_errWriteConflict || e.code == JET_errWriteConflictPrimaryIndex {
+                self.stats.write_conflicts += 1;
+            } else {
+                self.stats.version_store_exhausted += 1;
+            }
+            self.stats.retries += 1;
+            warn!("transaction attempt {} failed, retrying in {:?}: {}", attempt, backoff, e);
+
+            thread::sleep(backoff);
+            self.stats.total_backoff += backoff;
+            backoff = (backoff * 2).min(self.policy.max_backoff);
+            attempt += 1;
+        }
+    }
+}
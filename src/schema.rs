@@ -0,0 +1,271 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+use super::util::*;
+
+use std::mem::{size_of, MaybeUninit};
+
+/// The type of data stored in a column.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColumnType {
+    Bit,
+    UnsignedByte,
+    Short,
+    UnsignedShort,
+    Long,
+    UnsignedLong,
+    LongLong,
+    Currency,
+    IEEESingle,
+    IEEEDouble,
+    DateTime,
+    GUID,
+    Binary,
+    Text,
+    LongBinary,
+    LongText,
+}
+
+impl ColumnType {
+    pub fn coltyp(self) -> JET_COLTYP {
+        match self {
+            ColumnType::Bit => JET_coltypBit,
+            ColumnType::UnsignedByte => JET_coltypUnsignedByte,
+            ColumnType::Short => JET_coltypShort,
+            ColumnType::UnsignedShort => JET_coltypUnsignedShort,
+            ColumnType::Long => JET_coltypLong,
+            ColumnType::UnsignedLong => JET_coltypUnsignedLong,
+            ColumnType::LongLong => JET_coltypLongLong,
+            ColumnType::Currency => JET_coltypCurrency,
+            ColumnType::IEEESingle => JET_coltypIEEESingle,
+            ColumnType::IEEEDouble => JET_coltypIEEEDouble,
+            ColumnType::DateTime => JET_coltypDateTime,
+            ColumnType::GUID => JET_coltypGUID,
+            ColumnType::Binary => JET_coltypBinary,
+            ColumnType::Text => JET_coltypText,
+            ColumnType::LongBinary => JET_coltypLongBinary,
+            ColumnType::LongText => JET_coltypLongText,
+        }
+    }
+
+    pub fn is_text(self) -> bool {
+        match self {
+            ColumnType::Text | ColumnType::LongText => true,
+            _ => false,
+        }
+    }
+}
+
+/// The definition of a column, for `TableDef` or `JetTable::add_column`.
+#[derive(Debug, Clone)]
+pub struct ColumnDef {
+    pub name: WideString,
+    pub column_type: ColumnType,
+    /// Maximum size in bytes, or zero for the default for the type.
+    pub max_size: u32,
+    pub grbit: JET_GRBIT,
+    pub default: Option<Vec<u8>>,
+}
+
+impl ColumnDef {
+    pub fn new<S: Into<WideString>>(name: S, column_type: ColumnType) -> ColumnDef {
+        ColumnDef {
+            name: name.into(),
+            column_type,
+            max_size: 0,
+            grbit: JET_bitNil,
+            default: None,
+        }
+    }
+
+    pub fn max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn fixed(mut self) -> Self {
+        self.grbit |= JET_bitColumnFixed;
+        self
+    }
+
+    pub fn tagged(mut self) -> Self {
+        self.grbit |= JET_bitColumnTagged;
+        self
+    }
+
+    pub fn multi_valued(mut self) -> Self {
+        self.grbit |= JET_bitColumnMultiValued | JET_bitColumnTagged;
+        self
+    }
+
+    pub fn autoincrement(mut self) -> Self {
+        self.grbit |= JET_bitColumnAutoincrement;
+        self
+    }
+
+    /// Escrow columns must have a default value; see `default_value`.
+    pub fn escrow(mut self) -> Self {
+        self.grbit |= JET_bitColumnEscrowUpdate | JET_bitColumnFixed;
+        self
+    }
+
+    pub fn version(mut self) -> Self {
+        self.grbit |= JET_bitColumnVersion;
+        self
+    }
+
+    pub fn not_null(mut self) -> Self {
+        self.grbit |= JET_bitColumnNotNULL;
+        self
+    }
+
+    pub fn default_value<T: Copy>(mut self, value: &T) -> Self {
+        self.default = Some(byte_slice(value).to_vec());
+        self
+    }
+
+    pub fn default_bytes(mut self, value: Vec<u8>) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    pub(crate) fn to_raw(&self) -> JET_COLUMNDEF {
+        unsafe {
+            let mut def = MaybeUninit::<JET_COLUMNDEF>::zeroed().assume_init();
+            def.cbStruct = size_of::<JET_COLUMNDEF>() as u32;
+            def.coltyp = self.column_type.coltyp();
+            if self.column_type.is_text() {
+                // Text is always stored as UTF-16, to match how this library reads it back.
+                def.cp = 1200;
+            }
+            def.cbMax = self.max_size;
+            def.grbit = self.grbit;
+            def
+        }
+    }
+}
+
+/// A column of an existing table, as returned by `JetTable::list_columns`.
+#[derive(Debug, Clone)]
+pub struct ColumnInfo {
+    pub name: WideString,
+    pub column_id: JET_COLUMNID,
+    pub coltyp: JET_COLTYP,
+    /// The code page of text columns: 1200 for UTF-16, or 1252 for ASCII / Windows-1252.
+    pub codepage: u16,
+    pub grbit: JET_GRBIT,
+}
+
+/// One column of an index key.
+#[derive(Debug, Clone)]
+pub struct KeySegment {
+    pub column: WideString,
+    pub descending: bool,
+}
+
+/// The definition of an index, for `TableDef` or `JetTable::create_index`.
+#[derive(Debug, Clone)]
+pub struct IndexDef {
+    pub name: WideString,
+    pub segments: Vec<KeySegment>,
+    pub grbit: JET_GRBIT,
+    /// Percentage of each page to fill initially, or zero for the default.
+    pub density: u32,
+}
+
+impl IndexDef {
+    pub fn new<S: Into<WideString>>(name: S) -> IndexDef {
+        IndexDef {
+            name: name.into(),
+            segments: vec![],
+            grbit: JET_bitNil,
+            density: 0,
+        }
+    }
+
+    pub fn ascending<S: Into<WideString>>(mut self, column: S) -> Self {
+        self.segments.push(KeySegment { column: column.into(), descending: false });
+        self
+    }
+
+    pub fn descending<S: Into<WideString>>(mut self, column: S) -> Self {
+        self.segments.push(KeySegment { column: column.into(), descending: true });
+        self
+    }
+
+    pub fn primary(mut self) -> Self {
+        self.grbit |= JET_bitIndexPrimary;
+        self
+    }
+
+    pub fn unique(mut self) -> Self {
+        self.grbit |= JET_bitIndexUnique;
+        self
+    }
+
+    pub fn ignore_null(mut self) -> Self {
+        self.grbit |= JET_bitIndexIgnoreNull;
+        self
+    }
+
+    pub fn density(mut self, density: u32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// The key description string for JetCreateIndex: each column name prefixed with '+' or '-'
+    /// and NUL-terminated, with an extra NUL at the end.
+    pub(crate) fn key_template(&self) -> Vec<u16> {
+        let mut key = vec![];
+        for segment in &self.segments {
+            key.push(if segment.descending { '-' } else { '+' } as u16);
+            // as_ucs2_slice includes the NUL terminator
+            key.extend_from_slice(segment.column.as_ucs2_slice());
+        }
+        key.push(0);
+        key
+    }
+}
+
+/// The definition of a table, for `JetDatabase::create_table`.
+#[derive(Debug, Clone)]
+pub struct TableDef {
+    pub name: WideString,
+    pub columns: Vec<ColumnDef>,
+    pub indexes: Vec<IndexDef>,
+    /// Number of pages to allocate initially, or zero for the default.
+    pub initial_pages: u32,
+    /// Percentage of each page to fill initially, or zero for the default.
+    pub density: u32,
+}
+
+impl TableDef {
+    pub fn new<S: Into<WideString>>(name: S) -> TableDef {
+        TableDef {
+            name: name.into(),
+            columns: vec![],
+            indexes: vec![],
+            initial_pages: 0,
+            density: 0,
+        }
+    }
+
+    pub fn column(mut self, column: ColumnDef) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    pub fn index(mut self, index: IndexDef) -> Self {
+        self.indexes.push(index);
+        self
+    }
+}
+
+#[test]
+fn test_key_template() {
+    let index = IndexDef::new("ix").ascending("a").descending("bc");
+    let expected: Vec<u16> = "+a\0-bc\0\0".encode_utf16().collect();
+    assert_eq!(expected, index.key_template());
+}
@@ -4,16 +4,32 @@
 use winapi::um::esent::*;
 use super::*;
 
+use std::cell::Cell;
 use std::marker::PhantomData;
 use std::ptr::null;
 
+/// A session on an instance.
+///
+/// Sessions can be moved to another thread, but the engine doesn't allow a session to be used by
+/// more than one thread at the same time, so they are not `Sync`. Databases, tables and
+/// transactions borrow their session, so they have to stay on the thread that's using it. To
+/// share work between threads, give each thread its own session, such as from a `SessionPool`.
+///
+/// Sharing a session between threads doesn't compile:
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<esedb::JetSession>();
+/// ```
 #[derive(Debug)]
 pub struct JetSession<'a> {
     _lifetime: PhantomData<&'a JetInstance>,
+    _not_sync: PhantomData<Cell<()>>,
     sesid: JET_SESID,
+    transaction_depth: Cell<u32>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DatabaseAccessMode {
     ReadOnly,
     ReadWrite,
@@ -24,12 +40,27 @@ impl<'a> JetSession<'a> {
         assert!(sesid != JET_sesidNil);
         JetSession {
             _lifetime: PhantomData,
+            _not_sync: PhantomData,
             sesid,
+            transaction_depth: Cell::new(0),
         }
     }
 
     pub fn open_database<'b>(&'b mut self, path: &WideString, mode: DatabaseAccessMode)
             -> Result<JetDatabase<'b>, JetError> {
+        let dbid = self.open_database_id(path, mode)?;
+        Ok(JetDatabase::new(self, dbid))
+    }
+
+    /// Create a new database file and open it for read/write access.
+    pub fn create_database<'b>(&'b mut self, path: &WideString)
+            -> Result<JetDatabase<'b>, JetError> {
+        let dbid = self.create_database_id(path)?;
+        Ok(JetDatabase::new(self, dbid))
+    }
+
+    pub(crate) fn open_database_id(&self, path: &WideString, mode: DatabaseAccessMode)
+            -> Result<JET_DBID, JetError> {
         debug!("attaching+opening JetDatabase from {:?}", path);
         let mut dbid = JET_dbidNil;
         unsafe {
@@ -37,11 +68,48 @@ impl<'a> JetSession<'a> {
                 DatabaseAccessMode::ReadOnly => JET_bitDbReadOnly,
                 DatabaseAccessMode::ReadWrite => JET_bitNil,
             };
-            jettry!(JetAttachDatabaseW(self.sesid, path.as_ptr(), bit));
+            // Attachment is per instance, so another session may already have attached it.
+            match JetAttachDatabaseW(self.sesid, path.as_ptr(), bit) {
+                JET_errSuccess => (),
+                JET_wrnDatabaseAttached => debug!("JetDatabase {:?} was already attached", path),
+                other => {
+                    let e = JetError::from(other);
+                    error!("JetAttachDatabaseW failed: {}", e);
+                    return Err(e);
+                }
+            }
             jettry!(JetOpenDatabaseW(self.sesid, path.as_ptr(), null(), &mut dbid, JET_bitNil));
         }
         debug!("opened JetDatabase {:?} = {:x}", path, dbid);
-        Ok(JetDatabase::new(self, dbid))
+        Ok(dbid)
+    }
+
+    pub(crate) fn create_database_id(&self, path: &WideString) -> Result<JET_DBID, JetError> {
+        debug!("creating JetDatabase at {:?}", path);
+        let mut dbid = JET_dbidNil;
+        unsafe {
+            jettry!(JetCreateDatabaseW(self.sesid, path.as_ptr(), null(), &mut dbid, JET_bitNil));
+        }
+        debug!("created JetDatabase {:?} = {:x}", path, dbid);
+        Ok(dbid)
+    }
+
+    /// Begin a transaction. Changes made by this session are only visible to other sessions once
+    /// it is committed, and are undone if it is dropped without committing.
+    ///
+    /// If a transaction is already open on the session, the new one is nested inside it, the same
+    /// as with `Transaction::begin_nested`.
+    pub fn begin_transaction<'b>(&'b self) -> Result<Transaction<'b>, JetError> {
+        Transaction::begin(self)
+    }
+
+    /// How many transactions are open on this session; zero if none are.
+    pub fn transaction_depth(&self) -> u32 {
+        self.transaction_depth.get()
+    }
+
+    pub(crate) fn set_transaction_depth(&self, depth: u32) {
+        self.transaction_depth.set(depth);
     }
 
     /// # Safety
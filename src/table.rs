@@ -5,6 +5,7 @@ use winapi::um::esent::*;
 use winapi::ctypes::c_void;
 
 use super::*;
+use super::enumerate::{collect_enumerated, enumerate_grbit, realloc_fn};
 use super::util::*;
 
 use std::ffi::OsString;
@@ -37,6 +38,27 @@ impl<'a> JetTable<'a> {
         self.tableid
     }
 
+    /// # Safety
+    /// If the value returned is stored, it is unsafe to use after the JetSession this table was
+    /// opened with has been dropped.
+    pub unsafe fn raw_session(&self) -> JET_SESID {
+        self.sesid
+    }
+
+    /// Start inserting a new record into the table.
+    ///
+    /// Unlike `escrow_update`, this doesn't need a transaction: outside of one, the engine wraps
+    /// each insert, replace or delete in its own implicit transaction. Use a `Transaction` to group
+    /// several changes so they are applied or rolled back together.
+    pub fn insert(&self) -> Result<RecordBuilder, JetError> {
+        RecordBuilder::insert(self)
+    }
+
+    /// Start editing the current record.
+    pub fn replace(&self) -> Result<RecordBuilder, JetError> {
+        RecordBuilder::replace(self)
+    }
+
     fn move_internal(&self, offset: i32, next_key: bool) -> Result<(), JetError> {
         let grbit = if next_key {
             JET_bitMoveKeyNE
@@ -65,8 +87,120 @@ impl<'a> JetTable<'a> {
         self.move_internal(JET_MoveLast, false)
     }
 
+    /// Get the bookmark of the current record, which can be used with `goto_bookmark` to return
+    /// to it later.
+    pub fn get_bookmark(&self) -> Result<Bookmark, JetError> {
+        let mut data = vec![0u8; BOOKMARK_MAX];
+        let mut nbytes = 0u32;
+        unsafe {
+            jettry!(JetGetBookmark(self.sesid, self.tableid, data.as_mut_ptr() as *mut c_void,
+                    data.len() as u32, &mut nbytes));
+        }
+        Ok(Bookmark::from(data[..nbytes as usize].to_vec()))
+    }
+
+    /// Move the cursor to the record identified by the given bookmark.
+    pub fn goto_bookmark(&self, bookmark: &Bookmark) -> Result<(), JetError> {
+        let data = bookmark.as_bytes();
+        unsafe {
+            jettry!(JetGotoBookmark(self.sesid, self.tableid, data.as_ptr() as *mut c_void,
+                    data.len() as u32));
+        }
+        Ok(())
+    }
+
+    /// Get the position of the current record in the current secondary index.
+    pub fn get_secondary_index_bookmark(&self) -> Result<SecondaryIndexBookmark, JetError> {
+        let mut key = vec![0u8; BOOKMARK_MAX];
+        let mut primary = vec![0u8; BOOKMARK_MAX];
+        let mut key_bytes = 0u32;
+        let mut primary_bytes = 0u32;
+        unsafe {
+            jettry!(JetGetSecondaryIndexBookmark(self.sesid, self.tableid,
+                    key.as_mut_ptr() as *mut c_void, key.len() as u32, &mut key_bytes,
+                    primary.as_mut_ptr() as *mut c_void, primary.len() as u32, &mut primary_bytes,
+                    JET_bitNil));
+        }
+        Ok(SecondaryIndexBookmark::new(key[..key_bytes as usize].to_vec(),
+                Bookmark::from(primary[..primary_bytes as usize].to_vec())))
+    }
+
+    /// Move the cursor to the index entry identified by the given secondary index bookmark. The
+    /// secondary index it was taken from must be the current index.
+    pub fn goto_secondary_index_bookmark(&self, bookmark: &SecondaryIndexBookmark)
+            -> Result<(), JetError> {
+        let key = bookmark.secondary_key();
+        let primary = bookmark.primary().as_bytes();
+        unsafe {
+            jettry!(JetGotoSecondaryIndexBookmark(self.sesid, self.tableid,
+                    key.as_ptr() as *mut c_void, key.len() as u32,
+                    primary.as_ptr() as *mut c_void, primary.len() as u32,
+                    JET_bitNil));
+        }
+        Ok(())
+    }
+
+    /// Get the approximate position of the current record within the current index.
+    pub fn record_position(&self) -> Result<RecordPosition, JetError> {
+        unsafe {
+            let mut recpos = MaybeUninit::<JET_RECPOS>::zeroed().assume_init();
+            recpos.cbStruct = size_of::<JET_RECPOS>() as u32;
+            jettry!(JetGetRecordPosition(self.sesid, self.tableid, &mut recpos, recpos.cbStruct));
+            Ok(RecordPosition {
+                entries_less_than: recpos.centriesLT,
+                entries_total: recpos.centriesTotal,
+            })
+        }
+    }
+
+    /// Move the cursor to the record at approximately the given fraction (from 0.0 to 1.0) of the
+    /// way through the current index.
+    pub fn goto_position(&self, fraction: f64) -> Result<(), JetError> {
+        // The engine only cares about the ratio, so pick a denominator with plenty of precision.
+        const SCALE: u32 = 1_000_000;
+        let fraction = fraction.max(0.).min(1.);
+        unsafe {
+            let mut recpos = MaybeUninit::<JET_RECPOS>::zeroed().assume_init();
+            recpos.cbStruct = size_of::<JET_RECPOS>() as u32;
+            recpos.centriesLT = (fraction * f64::from(SCALE)) as u32;
+            recpos.centriesTotal = SCALE;
+            jettry!(JetGotoPosition(self.sesid, self.tableid, &mut recpos));
+        }
+        Ok(())
+    }
+
+    /// Count the records in the current index from the current position onwards (or within the
+    /// index range, if one is set), stopping once `limit` records have been counted. A limit of
+    /// zero counts all of them.
+    pub fn index_record_count(&self, limit: u32) -> Result<u32, JetError> {
+        let mut count = 0u32;
+        unsafe { jettry!(JetIndexRecordCount(self.sesid, self.tableid, &mut count, limit)); }
+        Ok(count)
+    }
+
+    /// Get every non-NULL column value in the current record, including each value of
+    /// multi-valued columns.
+    pub fn enumerate_columns(&self, options: &EnumerateOptions)
+            -> Result<Vec<EnumeratedColumn>, JetError> {
+        let mut count = 0u32;
+        let mut columns: *mut JET_ENUMCOLUMN = null_mut();
+        unsafe {
+            jettry!(JetEnumerateColumns(self.sesid, self.tableid, 0, null_mut(),
+                    &mut count, &mut columns, realloc_fn(), null_mut(),
+                    options.max_value_size.unwrap_or(0), enumerate_grbit(options)));
+            Ok(collect_enumerated(count, columns))
+        }
+    }
+
     pub fn retrieve_column_bytes<T: Copy>(&self, column_id: JET_COLUMNID)
             -> Result<Vec<T>, JetError> {
+        self.retrieve_column_bytes_opt(column_id).map(Option::unwrap_or_default)
+    }
+
+    /// Like `retrieve_column_bytes`, but returns `None` if the column is NULL, as opposed to an
+    /// empty vector if it is set to a zero-length value.
+    pub fn retrieve_column_bytes_opt<T: Copy>(&self, column_id: JET_COLUMNID)
+            -> Result<Option<Vec<T>>, JetError> {
         let mut data: Vec<T> = vec![];
         let mut nbytes = 0u32;
         unsafe {
@@ -75,11 +209,11 @@ impl<'a> JetTable<'a> {
                     null_mut(), 0, &mut nbytes, JET_bitNil, null_mut())) {
                 Err(e) => match e.code {
                     JET_wrnBufferTruncated => (),
-                    // TODO: maybe this should return Result<Option<Vec... instead
-                    JET_wrnColumnNull => return Ok(data), // return empty vector
+                    JET_wrnColumnNull => return Ok(None),
                     _ => return Err(e),
                 },
-                Ok(()) => panic!("expected this call to fail"),
+                // zero-length value
+                Ok(()) => return Ok(Some(data)),
             }
             // T is inappropriate if it doesn't evenly divide the number of bytes in the column.
             assert_eq!(0, nbytes as usize % size_of::<T>());
@@ -88,38 +222,339 @@ impl<'a> JetTable<'a> {
                     data.as_mut_ptr() as *mut c_void, nbytes, null_mut(), JET_bitNil, null_mut()));
             data.set_len(nbytes as usize / size_of::<T>());
         }
-        Ok(data)
+        Ok(Some(data))
     }
 
+    /// Retrieve one value of a multi-valued column; `itag_sequence` 1 is the first. Returns `None`
+    /// if there is no such value.
+    pub fn retrieve_tagged_bytes(&self, column_id: JET_COLUMNID, itag_sequence: u32)
+            -> Result<Option<Vec<u8>>, JetError> {
+        unsafe {
+            let mut retinfo = MaybeUninit::<JET_RETINFO>::zeroed().assume_init();
+            retinfo.cbStruct = size_of::<JET_RETINFO>() as u32;
+            retinfo.itagSequence = itag_sequence;
+            let mut nbytes = 0u32;
+            match jetcall!(JetRetrieveColumn(self.sesid, self.tableid, column_id,
+                    null_mut(), 0, &mut nbytes, JET_bitNil, &mut retinfo)) {
+                Err(e) => match e.code {
+                    JET_wrnBufferTruncated => (),
+                    JET_wrnColumnNull => return Ok(None),
+                    _ => return Err(e),
+                },
+                Ok(()) => return Ok(Some(vec![])),
+            }
+            let mut data = vec![0u8; nbytes as usize];
+            jettry!(JetRetrieveColumn(self.sesid, self.tableid, column_id,
+                    data.as_mut_ptr() as *mut c_void, nbytes, &mut nbytes, JET_bitNil,
+                    &mut retinfo));
+            data.truncate(nbytes as usize);
+            Ok(Some(data))
+        }
+    }
+
+    /// Retrieve every value of a multi-valued column.
+    pub fn retrieve_multi_values(&self, column_id: JET_COLUMNID)
+            -> Result<Vec<Vec<u8>>, JetError> {
+        let mut values = vec![];
+        while let Some(value) = self.retrieve_tagged_bytes(column_id, values.len() as u32 + 1)? {
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// Read part of a (long value) column into `buf`, starting at the given byte offset. Returns
+    /// the number of bytes read and the total size of the column.
+    pub(crate) fn retrieve_column_at(&self, column_id: JET_COLUMNID, offset: u32, buf: &mut [u8])
+            -> Result<(usize, u64), JetError> {
+        unsafe {
+            let mut retinfo = MaybeUninit::<JET_RETINFO>::zeroed().assume_init();
+            retinfo.cbStruct = size_of::<JET_RETINFO>() as u32;
+            retinfo.ibLongValue = offset;
+            retinfo.itagSequence = 1;
+            let mut actual = 0u32;
+            match jetcall!(JetRetrieveColumn(self.sesid, self.tableid, column_id,
+                    buf.as_mut_ptr() as *mut c_void, buf.len() as u32, &mut actual,
+                    JET_bitNil, &mut retinfo)) {
+                Ok(()) => (),
+                Err(ref e) if e.code == JET_wrnBufferTruncated => (),
+                Err(ref e) if e.code == JET_wrnColumnNull => return Ok((0, 0)),
+                Err(e) => {
+                    error!("JetRetrieveColumn failed: {}", e);
+                    return Err(e);
+                }
+            }
+            // cbActual is the size of the data from the offset onwards.
+            let total = u64::from(offset) + u64::from(actual);
+            Ok((buf.len().min(actual as usize), total))
+        }
+    }
+
+    /// Write `data` to a (long value) column, either appending it or overwriting from the given
+    /// byte offset.
+    pub(crate) fn set_column_at(&self, column_id: JET_COLUMNID, offset: Option<u32>, data: &[u8])
+            -> Result<(), JetError> {
+        unsafe {
+            let mut setinfo = MaybeUninit::<JET_SETINFO>::zeroed().assume_init();
+            setinfo.cbStruct = size_of::<JET_SETINFO>() as u32;
+            setinfo.itagSequence = 1;
+            let grbit = match offset {
+                Some(offset) => {
+                    setinfo.ibLongValue = offset;
+                    JET_bitSetOverwriteLV
+                }
+                None => JET_bitSetAppendLV,
+            };
+            jettry!(JetSetColumn(self.sesid, self.tableid, column_id,
+                    data.as_ptr() as *const c_void, data.len() as u32, grbit, &setinfo));
+        }
+        Ok(())
+    }
+
+    /// Get a reader for streaming the contents of a long value column without retrieving it all
+    /// at once.
+    pub fn column_reader(&self, column_id: JET_COLUMNID) -> ColumnReader {
+        ColumnReader::new(self, column_id)
+    }
+
+    /// Get a writer for streaming data into a long value column. This must be used within a
+    /// prepared update.
+    pub fn column_writer(&self, column_id: JET_COLUMNID, mode: ColumnWriteMode) -> ColumnWriter {
+        ColumnWriter::new(self, column_id, mode)
+    }
+
+    /// Retrieve several columns from the current record with as few calls into the engine as
+    /// possible. Each request's buffer is reused as-is, and only those too small to hold their
+    /// value are grown and fetched again.
+    pub fn retrieve_columns(&self, requests: &mut [ColumnRequest]) -> Result<(), JetError> {
+        let mut pending: Vec<usize> = (0 .. requests.len()).collect();
+        while !pending.is_empty() {
+            let mut retrieve = pending.iter().map(|&i| {
+                let request = &mut requests[i];
+                request.data.clear();
+                unsafe {
+                    let mut r = MaybeUninit::<JET_RETRIEVECOLUMN>::zeroed().assume_init();
+                    r.columnid = request.column_id;
+                    r.pvData = request.data.as_mut_ptr() as *mut c_void;
+                    r.cbData = request.data.capacity() as u32;
+                    r.itagSequence = request.itag_sequence;
+                    r
+                }
+            }).collect::<Vec<_>>();
+
+            unsafe {
+                match jetcall!(JetRetrieveColumns(self.sesid, self.tableid,
+                        retrieve.as_mut_ptr(), retrieve.len() as u32)) {
+                    // Warnings for individual columns are checked below.
+                    Err(ref e) if e.code > 0 => (),
+                    Err(e) => {
+                        error!("JetRetrieveColumns failed: {}", e);
+                        return Err(e);
+                    }
+                    Ok(()) => (),
+                }
+            }
+
+            let mut truncated = vec![];
+            for (r, &i) in retrieve.iter().zip(pending.iter()) {
+                let request = &mut requests[i];
+                match r.err {
+                    JET_errSuccess => {
+                        unsafe { request.data.set_len(r.cbActual as usize); }
+                        request.is_null = false;
+                    }
+                    JET_wrnColumnNull => {
+                        request.is_null = true;
+                    }
+                    JET_wrnBufferTruncated => {
+                        request.data.reserve_exact(r.cbActual as usize);
+                        truncated.push(i);
+                    }
+                    err => return Err(JetError::from(err)),
+                }
+            }
+            pending = truncated;
+        }
+        Ok(())
+    }
+
+    /// Retrieve a string column. A NULL column gives an empty string; use `retrieve_wstring_opt`
+    /// to tell the two apart.
     pub fn retrieve_wstring(&self, column_id: JET_COLUMNID) -> Result<WideString, JetError> {
         let ucs2: Vec<u16> = self.retrieve_column_bytes(column_id)?;
         Ok(WideString::from(ucs2))
     }
 
+    pub fn retrieve_wstring_opt(&self, column_id: JET_COLUMNID)
+            -> Result<Option<WideString>, JetError> {
+        let ucs2: Option<Vec<u16>> = self.retrieve_column_bytes_opt(column_id)?;
+        Ok(ucs2.map(WideString::from))
+    }
+
     pub fn retrieve_string(&self, column_id: JET_COLUMNID) -> Result<OsString, JetError> {
         self.retrieve_wstring(column_id).map(|x| OsString::from(&x))
     }
 
+    pub fn retrieve_string_opt(&self, column_id: JET_COLUMNID)
+            -> Result<Option<OsString>, JetError> {
+        self.retrieve_wstring_opt(column_id).map(|x| x.map(|x| OsString::from(&x)))
+    }
+
+    /// Retrieve a fixed-size column. Fails with `JET_wrnColumnNull` if the column is NULL.
     pub fn retrieve<T: Copy>(&self, column_id: JET_COLUMNID) -> Result<T, JetError> {
+        match self.retrieve_opt(column_id)? {
+            Some(data) => Ok(data),
+            None => {
+                let e = JetError::from(JET_wrnColumnNull);
+                error!("JetRetrieveColumn failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Retrieve a fixed-size column, or `None` if it is NULL.
+    pub fn retrieve_opt<T: Copy>(&self, column_id: JET_COLUMNID) -> Result<Option<T>, JetError> {
+        unsafe {
+            let mut data = MaybeUninit::<T>::zeroed().assume_init();
+            let mut actual_bytes = 0;
+            match jetcall!(JetRetrieveColumn(self.sesid, self.tableid, column_id,
+                    &mut data as *mut _ as *mut c_void, size_of::<T>() as u32, &mut actual_bytes,
+                    JET_bitNil, null_mut())) {
+                Ok(()) => (),
+                Err(ref e) if e.code == JET_wrnColumnNull => return Ok(None),
+                Err(e) => {
+                    error!("JetRetrieveColumn failed: {}", e);
+                    return Err(e);
+                }
+            }
+            assert_eq!(size_of::<T>() as u32, actual_bytes);
+            Ok(Some(data))
+        }
+    }
+
+    /// Retrieve a fixed-size column from the copy buffer of the record being inserted or
+    /// replaced, rather than from the record in the table.
+    pub(crate) fn retrieve_copy<T: Copy>(&self, column_id: JET_COLUMNID) -> Result<T, JetError> {
         unsafe {
             let mut data = MaybeUninit::<T>::zeroed().assume_init();
             let mut actual_bytes = 0;
             jettry!(JetRetrieveColumn(self.sesid, self.tableid, column_id,
                     &mut data as *mut _ as *mut c_void, size_of::<T>() as u32, &mut actual_bytes,
-                    JET_bitNil, null_mut()));
+                    JET_bitRetrieveCopy, null_mut()));
             assert_eq!(size_of::<T>() as u32, actual_bytes);
             Ok(data)
         }
     }
 
+    /// Atomically add `delta` to an escrow column of the current record, returning the value it
+    /// had before. Escrow updates from different sessions don't conflict with each other, but they
+    /// must be done inside a transaction on the session the table was opened with; passing a
+    /// transaction from any other session fails with `JET_errInvalidSesid`.
+    ///
+    /// `T` should be `i32` or `i64`, matching the column type.
+    pub fn escrow_update<T: Copy>(&self, transaction: &Transaction, column_id: JET_COLUMNID,
+            delta: T) -> Result<T, JetError> {
+        if transaction.sesid() != self.sesid {
+            error!("escrow update on JetTable {:x} with a transaction from another session",
+                self.tableid);
+            return Err(JetError::from(JET_errInvalidSesid));
+        }
+        unsafe {
+            let mut old = MaybeUninit::<T>::zeroed().assume_init();
+            let mut actual_bytes = 0;
+            jettry!(JetEscrowUpdate(self.sesid, self.tableid, column_id,
+                    &delta as *const T as *mut c_void, size_of::<T>() as u32,
+                    &mut old as *mut T as *mut c_void, size_of::<T>() as u32, &mut actual_bytes,
+                    JET_bitNil));
+            assert_eq!(size_of::<T>() as u32, actual_bytes);
+            Ok(old)
+        }
+    }
+
     pub fn get_column_id(&self, column_name: &WideString) -> Result<JET_COLUMNID, JetError> {
+        self.get_column_def(column_name).map(|info| info.columnid)
+    }
+
+    /// Get the ID, type, size and flags of a column.
+    pub fn get_column_def(&self, column_name: &WideString) -> Result<JET_COLUMNDEF, JetError> {
         unsafe {
             let mut info = MaybeUninit::<JET_COLUMNDEF>::zeroed().assume_init();
             info.cbStruct = size_of::<JET_COLUMNDEF>() as u32;
             jettry!(JetGetTableColumnInfoW(self.sesid, self.tableid, column_name.as_ptr(),
                     &mut info as *mut _ as *mut c_void, info.cbStruct, JET_ColInfo));
-            Ok(info.columnid)
+            Ok(info)
+        }
+    }
+
+    /// List all the columns of the table.
+    pub fn list_columns(&self) -> Result<Vec<ColumnInfo>, JetError> {
+        let list = unsafe {
+            let mut list = MaybeUninit::<JET_COLUMNLIST>::zeroed().assume_init();
+            list.cbStruct = size_of::<JET_COLUMNLIST>() as u32;
+            jettry!(JetGetTableColumnInfoW(self.sesid, self.tableid, null(),
+                    &mut list as *mut _ as *mut c_void, list.cbStruct, JET_ColInfoList));
+            list
+        };
+
+        // The list is returned as a temporary table, which is closed when this is dropped.
+        let temp = JetTable {
+            _lifetime: PhantomData,
+            sesid: self.sesid,
+            tableid: list.tableid,
+        };
+        let mut columns = Vec::with_capacity(list.cRecord as usize);
+        if list.cRecord == 0 {
+            return Ok(columns);
+        }
+        temp.move_first()?;
+        loop {
+            columns.push(ColumnInfo {
+                name: temp.retrieve_wstring(list.columnidcolumnname)?,
+                column_id: temp.retrieve(list.columnidcolumnid)?,
+                coltyp: temp.retrieve(list.columnidcoltyp)?,
+                codepage: temp.retrieve(list.columnidCp)?,
+                grbit: temp.retrieve(list.columnidgrbit)?,
+            });
+            match temp.move_next() {
+                Ok(()) => (),
+                Err(ref e) if e.code == JET_errNoCurrentRecord => break,
+                Err(e) => return Err(e),
+            }
         }
+        Ok(columns)
+    }
+
+    /// Add a column to the table, returning its ID.
+    pub fn add_column(&self, def: &ColumnDef) -> Result<JET_COLUMNID, JetError> {
+        let raw = def.to_raw();
+        let (default, default_len) = match def.default {
+            Some(ref data) => (data.as_ptr() as *const c_void, data.len() as u32),
+            None => (null(), 0),
+        };
+        let mut column_id = 0;
+        unsafe {
+            jettry!(JetAddColumnW(self.sesid, self.tableid, def.name.as_ptr(), &raw,
+                    default, default_len, &mut column_id));
+        }
+        Ok(column_id)
+    }
+
+    pub fn delete_column(&self, column_name: &WideString) -> Result<(), JetError> {
+        unsafe { jettry!(JetDeleteColumnW(self.sesid, self.tableid, column_name.as_ptr())); }
+        Ok(())
+    }
+
+    pub fn create_index(&self, def: &IndexDef) -> Result<(), JetError> {
+        let key = def.key_template();
+        unsafe {
+            jettry!(JetCreateIndexW(self.sesid, self.tableid, def.name.as_ptr(), def.grbit,
+                    key.as_ptr(), (key.len() * size_of::<u16>()) as u32, def.density));
+        }
+        Ok(())
+    }
+
+    pub fn delete_index(&self, index_name: &WideString) -> Result<(), JetError> {
+        unsafe { jettry!(JetDeleteIndexW(self.sesid, self.tableid, index_name.as_ptr())); }
+        Ok(())
     }
 
     pub fn select_index(&self, index_name: &WideString) -> Result<(), JetError> {
@@ -127,6 +562,11 @@ impl<'a> JetTable<'a> {
         Ok(())
     }
 
+    pub fn select_primary_index(&self) -> Result<(), JetError> {
+        unsafe { jettry!(JetSetCurrentIndexW(self.sesid, self.tableid, null())); }
+        Ok(())
+    }
+
     fn seek_internal(&self, seek_type: SeekType, data: &[u8]) -> Result<bool, JetError> {
         let seek_grbit = match seek_type {
             SeekType::Equal => JET_bitSeekEQ,
@@ -163,6 +603,65 @@ impl<'a> JetTable<'a> {
         self.seek_internal(seek_type, slice_transmute(wstr.as_ucs2_slice()))
     }
 
+    /// Limit the index range to end just before the record with the given bookmark, so moving
+    /// onto or past it fails with `JET_errNoCurrentRecord`. Returns false if the current record is
+    /// already outside the range. The primary index must be the current index.
+    pub(crate) fn set_range_end(&self, end: &Bookmark) -> Result<bool, JetError> {
+        let key = end.as_bytes();
+        unsafe {
+            jettry!(JetMakeKey(self.sesid, self.tableid, key.as_ptr() as *const c_void,
+                    key.len() as u32, JET_bitNormalizedKey));
+            match jetcall!(JetSetIndexRange(self.sesid, self.tableid, JET_bitRangeUpperLimit)) {
+                Ok(()) => Ok(true),
+                Err(ref e) if e.code == JET_errNoCurrentRecord => Ok(false),
+                Err(e) => {
+                    error!("JetSetIndexRange failed: {}", e);
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    /// Remove the index range set by `set_range_end` (or by seeking with `SeekType::EqualOnly`).
+    pub(crate) fn remove_index_range(&self) -> Result<(), JetError> {
+        unsafe { jettry!(JetSetIndexRange(self.sesid, self.tableid, JET_bitRangeRemove)); }
+        Ok(())
+    }
+
+    /// Delete the current record. The cursor is left positioned where the record was, so
+    /// `move_next` or `move_prev` go to its neighbors.
+    pub fn delete_current(&self) -> Result<(), JetError> {
+        unsafe { jettry!(JetDelete(self.sesid, self.tableid)); }
+        Ok(())
+    }
+
+    /// Delete the current record and every one after it in the current index range (such as one
+    /// set up by seeking with `SeekType::EqualOnly`), or to the end of the index if no range is
+    /// set. Returns the number of records deleted.
+    ///
+    /// Each record is deleted in its own implicit transaction unless a `Transaction` is open on
+    /// the session, so without one a failure part way through leaves the earlier records deleted.
+    pub fn delete_range(&self) -> Result<u64, JetError> {
+        let mut count = 0;
+        loop {
+            match unsafe { jetcall!(JetDelete(self.sesid, self.tableid)) } {
+                Ok(()) => count += 1,
+                Err(ref e) if e.code == JET_errNoCurrentRecord && count == 0 => break,
+                Err(e) => {
+                    error!("JetDelete failed: {}", e);
+                    return Err(e);
+                }
+            }
+            match self.move_next() {
+                Ok(()) => (),
+                Err(ref e) if e.code == JET_errNoCurrentRecord => break,
+                Err(e) => return Err(e),
+            }
+        }
+        debug!("deleted {} records from JetTable {:x}", count, self.tableid);
+        Ok(count)
+    }
+
     fn update_internal(&self, column_id: JET_COLUMNID, data: &[u8]) -> Result<(), JetError> {
         unsafe {
             jetcall!(JetSetColumn(self.sesid, self.tableid, column_id,
@@ -170,6 +669,8 @@ impl<'a> JetTable<'a> {
         }
     }
 
+    /// Set a column of the record being inserted or replaced. This must be done within a prepared
+    /// update; see `insert` and `replace`.
     pub fn update<T: Copy>(&self, column_id: JET_COLUMNID, data: &T)
             -> Result<(), JetError> {
         self.update_internal(column_id, byte_slice(data))
@@ -184,6 +685,107 @@ impl<'a> JetTable<'a> {
             -> Result<(), JetError> {
         self.update_internal(column_id, slice_transmute(wstr.as_ucs2_slice()))
     }
+
+    pub fn update_null(&self, column_id: JET_COLUMNID) -> Result<(), JetError> {
+        unsafe {
+            jetcall!(JetSetColumn(self.sesid, self.tableid, column_id, null(), 0, JET_bitNil,
+                    null()))
+        }
+    }
+
+    /// Add a new value to a multi-valued column of the record being inserted or replaced.
+    pub fn add_multi_value(&self, column_id: JET_COLUMNID, data: &[u8]) -> Result<(), JetError> {
+        unsafe {
+            let mut setinfo = MaybeUninit::<JET_SETINFO>::zeroed().assume_init();
+            setinfo.cbStruct = size_of::<JET_SETINFO>() as u32;
+            // itagSequence 0 means to add a new value rather than replace one
+            setinfo.itagSequence = 0;
+            jetcall!(JetSetColumn(self.sesid, self.tableid, column_id,
+                    data.as_ptr() as *const c_void, data.len() as u32, JET_bitNil, &setinfo))
+        }
+    }
+
+    /// Remove every value of a multi-valued column of the record being inserted or replaced.
+    pub fn clear_multi_values(&self, column_id: JET_COLUMNID) -> Result<(), JetError> {
+        unsafe {
+            let mut retinfo = MaybeUninit::<JET_RETINFO>::zeroed().assume_init();
+            retinfo.cbStruct = size_of::<JET_RETINFO>() as u32;
+            retinfo.itagSequence = 1;
+            let mut setinfo = MaybeUninit::<JET_SETINFO>::zeroed().assume_init();
+            setinfo.cbStruct = size_of::<JET_SETINFO>() as u32;
+            setinfo.itagSequence = 1;
+            loop {
+                let mut actual = 0u32;
+                match jetcall!(JetRetrieveColumn(self.sesid, self.tableid, column_id, null_mut(),
+                        0, &mut actual, JET_bitRetrieveCopy, &mut retinfo)) {
+                    Err(ref e) if e.code == JET_wrnColumnNull => return Ok(()),
+                    Ok(()) => (),
+                    Err(ref e) if e.code == JET_wrnBufferTruncated => (),
+                    Err(e) => {
+                        error!("JetRetrieveColumn failed: {}", e);
+                        return Err(e);
+                    }
+                }
+                // Setting a value to NULL removes it, and the ones after it move down.
+                jettry!(JetSetColumn(self.sesid, self.tableid, column_id, null(), 0, JET_bitNil,
+                        &setinfo));
+            }
+        }
+    }
+}
+
+/// One column to be fetched by `JetTable::retrieve_columns`, along with the buffer it is read
+/// into. Requests can be kept around and reused for each record to avoid reallocating.
+#[derive(Debug, Clone)]
+pub struct ColumnRequest {
+    pub column_id: JET_COLUMNID,
+    /// Which value of a multi-valued column to get; 1 is the first.
+    pub itag_sequence: u32,
+    /// The value retrieved; empty if the column is NULL.
+    pub data: Vec<u8>,
+    pub is_null: bool,
+}
+
+impl ColumnRequest {
+    /// Make a request for the given column, with an initial buffer capacity in bytes.
+    pub fn new(column_id: JET_COLUMNID, capacity: usize) -> ColumnRequest {
+        ColumnRequest {
+            column_id,
+            itag_sequence: 1,
+            data: Vec::with_capacity(capacity),
+            is_null: false,
+        }
+    }
+
+    /// Interpret the retrieved value as a fixed-size value.
+    pub fn get<T: Copy>(&self) -> Option<T> {
+        if self.is_null || self.data.len() != size_of::<T>() {
+            return None;
+        }
+        Some(unsafe { (self.data.as_ptr() as *const T).read_unaligned() })
+    }
+
+    /// Interpret the retrieved value as a fixed-size value, like `JetTable::retrieve`. Fails with
+    /// `JET_wrnColumnNull` if the column is NULL, or `JET_errInvalidBufferSize` if the value isn't
+    /// the size of `T`.
+    pub fn value<T: Copy>(&self) -> Result<T, JetError> {
+        let code = if self.is_null {
+            JET_wrnColumnNull
+        } else if self.data.len() != size_of::<T>() {
+            JET_errInvalidBufferSize
+        } else {
+            return Ok(unsafe { (self.data.as_ptr() as *const T).read_unaligned() });
+        };
+        let e = JetError::from(code);
+        error!("column {} can't be read as a {}-byte value: {}", self.column_id, size_of::<T>(), e);
+        Err(e)
+    }
+
+    /// Interpret the retrieved value as a string, like `JetTable::retrieve_wstring`. A NULL column
+    /// gives an empty string.
+    pub fn wstring(&self) -> WideString {
+        WideString::from(ucs2_from_bytes(&self.data))
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -196,6 +798,25 @@ pub enum SeekType {
     ClosestLesser,
 }
 
+/// The approximate position of a record within an index, as returned by
+/// `JetTable::record_position`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RecordPosition {
+    pub entries_less_than: u32,
+    pub entries_total: u32,
+}
+
+impl RecordPosition {
+    /// The position as a fraction from 0.0 to 1.0, suitable for showing progress.
+    pub fn fraction(&self) -> f64 {
+        if self.entries_total == 0 {
+            0.
+        } else {
+            f64::from(self.entries_less_than) / f64::from(self.entries_total)
+        }
+    }
+}
+
 impl<'a> Drop for JetTable<'a> {
     fn drop(&mut self) {
         debug!("closing JetTable {:x}", self.tableid);
@@ -0,0 +1,118 @@
+// Extensible Storage Engine database library for Rust
+// Copyright 2016-2019 by William R. Fraser
+
+use winapi::um::esent::*;
+use super::*;
+
+/// How durably a transaction should be committed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommitMode {
+    /// Wait for the commit to be flushed to the log on disk before returning.
+    Durable,
+    /// Return immediately; the commit is flushed to disk some time later, so it can be lost if the
+    /// process crashes.
+    Lazy,
+}
+
+/// An open transaction on a session, created with `JetSession::begin_transaction`.
+///
+/// It must be finished by calling `commit`. If it is dropped without committing, it is rolled back.
+/// Transactions can be nested by calling `begin_nested`; the outer transaction can't be used until
+/// the nested one is finished.
+///
+/// Only the innermost open transaction on a session can be committed; committing any other fails
+/// with `JET_errInvalidOperation` and rolls it back. Rolling back (or dropping) a transaction also
+/// rolls back every transaction still open inside it.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    session: &'a JetSession<'a>,
+    level: u32,
+    finished: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn begin(session: &'a JetSession<'a>) -> Result<Transaction<'a>, JetError> {
+        let sesid = unsafe { session.raw() };
+        let level = session.transaction_depth() + 1;
+        debug!("beginning transaction level {} on JetSession {:x}", level, sesid);
+        unsafe { jettry!(JetBeginTransaction(sesid)); }
+        session.set_transaction_depth(level);
+        Ok(Transaction {
+            session,
+            level,
+            finished: false,
+        })
+    }
+
+    /// Start a transaction nested inside this one.
+    pub fn begin_nested<'b>(&'b mut self) -> Result<Transaction<'b>, JetError> {
+        Transaction::begin(self.session)
+    }
+
+    /// How deeply nested this transaction is; the outermost one is level 1.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Commit the transaction. If the commit fails, the transaction is rolled back.
+    pub fn commit(mut self, mode: CommitMode) -> Result<(), JetError> {
+        let grbit = match mode {
+            CommitMode::Durable => JET_bitNil,
+            CommitMode::Lazy => JET_bitCommitLazyFlush,
+        };
+        let sesid = unsafe { self.session.raw() };
+        // On any failure, leave `finished` unset so dropping the transaction rolls it back.
+        let depth = self.session.transaction_depth();
+        if self.level != depth {
+            // Committing would commit whichever transaction is innermost, not this one.
+            let code = if self.level > depth {
+                JET_errNotInTransaction
+            } else {
+                JET_errInvalidOperation
+            };
+            let e = JetError::from(code);
+            error!("can't commit transaction level {} on JetSession {:x} at depth {}: {}",
+                self.level, sesid, depth, e);
+            return Err(e);
+        }
+        debug!("committing transaction level {} on JetSession {:x}", self.level, sesid);
+        unsafe { jettry!(JetCommitTransaction(sesid, grbit)); }
+        self.session.set_transaction_depth(self.level - 1);
+        self.finished = true;
+        Ok(())
+    }
+
+    pub(crate) fn sesid(&self) -> JET_SESID {
+        unsafe { self.session.raw() }
+    }
+
+    /// Undo all changes made in the transaction. This is the same as dropping it, except errors are
+    /// returned.
+    pub fn rollback(mut self) -> Result<(), JetError> {
+        self.finished = true;
+        self.rollback_internal()
+    }
+
+    fn rollback_internal(&self) -> Result<(), JetError> {
+        let sesid = unsafe { self.session.raw() };
+        // Roll back any transactions still open inside this one first. If an outer transaction
+        // was already rolled back, this one went with it.
+        while self.session.transaction_depth() >= self.level {
+            let depth = self.session.transaction_depth();
+            debug!("rolling back transaction level {} on JetSession {:x}", depth, sesid);
+            unsafe { jettry!(JetRollback(sesid, JET_bitNil)); }
+            self.session.set_transaction_depth(depth - 1);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(e) = self.rollback_internal() {
+                error!("failed to roll back transaction: {}", e);
+            }
+        }
+    }
+}
@@ -17,3 +17,33 @@ pub fn slice_transmute<T: Copy, U: Copy>(src: &[T]) -> &[U] {
 pub fn byte_slice<T: Copy>(data: &T) -> &[u8] {
     unsafe { slice::from_raw_parts(data as *const T as *const u8, size_of::<T>()) }
 }
+
+/// The code page of text columns holding UTF-16.
+pub const CP_UNICODE: u16 = 1200;
+
+/// Convert the bytes of a UTF-16 text column value to UCS-2 code units. A trailing odd byte is
+/// ignored.
+pub fn ucs2_from_bytes(data: &[u8]) -> Vec<u16> {
+    data.chunks(2)
+        .filter(|c| c.len() == 2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect()
+}
+
+/// Decode the value of a text column with the given code page, up to the first NUL if there is one.
+pub fn decode_text(data: &[u8], codepage: u16) -> String {
+    if codepage == CP_UNICODE {
+        let ucs2 = ucs2_from_bytes(data);
+        let end = ucs2.iter().position(|&c| c == 0).unwrap_or(ucs2.len());
+        String::from_utf16_lossy(&ucs2[..end])
+    } else {
+        // ASCII / Windows-1252; treat it as Latin-1, which agrees on everything but 0x80-0x9F.
+        data.iter().take_while(|&&b| b != 0).map(|&b| b as char).collect()
+    }
+}
+
+#[test]
+fn test_decode_text() {
+    assert_eq!("hi", decode_text(&[0x68, 0, 0x69, 0, 0, 0, 0x78, 0], CP_UNICODE));
+    assert_eq!("caf\u{e9}", decode_text(&[0x63, 0x61, 0x66, 0xe9], 1252));
+}
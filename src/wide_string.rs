@@ -67,8 +67,11 @@ impl From<String> for WideString {
 }
 
 impl From<Vec<u16>> for WideString {
-    fn from(ucs2: Vec<u16>) -> WideString {
-        assert_eq!(0, ucs2[ucs2.len() - 1]);
+    fn from(mut ucs2: Vec<u16>) -> WideString {
+        // Values stored in the database aren't necessarily NUL-terminated (or even non-empty).
+        if ucs2.last() != Some(&0) {
+            ucs2.push(0);
+        }
         WideString {
             ucs2,
         }
@@ -80,3 +83,10 @@ impl<'a> From<&'a WideString> for OsString {
         OsString::from_wide(&s.ucs2[0..s.ucs2.len() - 1]) // remove the trailing NUL
     }
 }
+
+#[test]
+fn test_from_unterminated() {
+    assert_eq!("", WideString::from(vec![]).to_string_lossy());
+    assert_eq!("hi", WideString::from(vec![0x68, 0x69]).to_string_lossy());
+    assert_eq!(3, WideString::from(vec![0x68, 0x69, 0]).len());
+}